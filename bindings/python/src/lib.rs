@@ -0,0 +1,194 @@
+
+//  SMTools Python bindings:
+//  Signatures, dialects and ELF/PE scanning from plain Python, no Binary Ninja needed.
+//  Errors are raised as ValueError (bad input) or OSError (unreadable files).
+
+//  Same style as the plugin; useless_conversion fires inside the pyo3 macros
+#![allow(clippy::needless_return, clippy::useless_conversion)]
+
+use std::path::Path;
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use smtools_core::dialect::Dialect;
+use smtools_core::loader::{load_image, Image, ImageFormat};
+use smtools_core::scan::scan_buffer;
+use smtools_core::sigbyte::SigByte;
+use smtools_core::signature::{parse_full_signature, Signature};
+
+#[pyclass(name = "Signature", module = "smtools_core")]
+struct PySignature {
+    inner: Signature,
+}
+
+#[pyclass(name = "Image", module = "smtools_core")]
+struct PyImage {
+    inner: Image,
+}
+
+fn parse(text: &str) -> PyResult<Signature> {
+    return parse_full_signature(text.to_owned()).map_err(PyValueError::new_err);
+}
+
+fn dialect(name: &str) -> PyResult<Dialect> {
+    return Dialect::from_name(name).ok_or(PyValueError::new_err(format!("Unknown dialect '{0}'", name)));
+}
+
+//  Accept either a Signature or its text wherever a signature is expected
+fn signature_arg(signature: &Bound<'_, PyAny>) -> PyResult<Signature> {
+    if let Ok(parsed) = signature.downcast::<PySignature>() {
+        return Ok(parsed.borrow().inner.to_owned());
+    }
+    return parse(&signature.extract::<String>()?);
+}
+
+#[pymethods]
+impl PySignature
+{
+    #[new]
+    fn new(text: &str) -> PyResult<PySignature> {
+        return Ok(PySignature { inner: parse(text)? });
+    }
+
+    //  Concrete bytes as ints, wildcards as None
+    #[getter]
+    fn bytes(&self) -> Vec<Option<u8>> {
+        return self.inner.bytes.iter().map(|b| match b {
+            SigByte::Wildcard => None,
+            SigByte::Match(value) => Some(*value),
+        }).collect();
+    }
+
+    #[getter]
+    fn wildcards(&self) -> usize {
+        return self.inner.bytes.iter().filter(|b| **b == SigByte::Wildcard).count();
+    }
+
+    #[getter]
+    fn resolve(&self) -> String {
+        return self.inner.to_str_resolve();
+    }
+
+    #[pyo3(signature = (dialect_name = "generic"))]
+    fn format(&self, dialect_name: &str) -> PyResult<String> {
+        return Ok(dialect(dialect_name)?.format(&self.inner.bytes));
+    }
+
+    fn __len__(&self) -> usize {
+        return self.inner.bytes.len();
+    }
+
+    fn __str__(&self) -> String {
+        return self.inner.to_str_full();
+    }
+
+    fn __repr__(&self) -> String {
+        return format!("Signature('{0}')", self.inner.to_str_full());
+    }
+}
+
+#[pymethods]
+impl PyImage
+{
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<PyImage> {
+        if !Path::new(path).is_file() {
+            return Err(PyOSError::new_err(format!("No such file: {0}", path)));
+        }
+        return Ok(PyImage { inner: load_image(Path::new(path)).map_err(PyValueError::new_err)? });
+    }
+
+    #[staticmethod]
+    fn parse(data: &[u8]) -> PyResult<PyImage> {
+        return Ok(PyImage { inner: Image::parse(data).map_err(PyValueError::new_err)? });
+    }
+
+    #[getter]
+    fn format(&self) -> &'static str {
+        match self.inner.format {
+            ImageFormat::Elf => "elf",
+            ImageFormat::Pe => "pe",
+        }
+    }
+
+    //  Gamedata platform key
+    #[getter]
+    fn platform(&self) -> String {
+        return self.inner.platform();
+    }
+
+    #[getter]
+    fn is_64(&self) -> bool {
+        return self.inner.is_64;
+    }
+
+    #[getter]
+    fn base(&self) -> u64 {
+        return self.inner.base;
+    }
+
+    //  (name, address, size, executable) for every section with data
+    #[getter]
+    fn sections(&self) -> Vec<(String, u64, usize, bool)> {
+        return self.inner.sections.iter()
+            .map(|s| (s.name.to_owned(), s.address, s.data.len(), s.executable))
+            .collect();
+    }
+
+    #[pyo3(signature = (signature, code_only = false, limit = 50))]
+    fn scan(&self, signature: &Bound<'_, PyAny>, code_only: bool, limit: usize) -> PyResult<Vec<u64>> {
+        let signature = signature_arg(signature)?;
+        return Ok(self.inner.scan(&signature.bytes, code_only, limit.max(1)));
+    }
+
+    //  Follow the signature's resolve chain from `address`, returning the target
+    fn resolve(&self, address: u64, signature: &Bound<'_, PyAny>) -> PyResult<u64> {
+        let signature = signature_arg(signature)?;
+        let hops = self.inner.resolve(address, &signature.resolve).map_err(PyValueError::new_err)?;
+        return Ok(hops.last().copied().unwrap_or(address));
+    }
+
+    fn read<'py>(&self, py: Python<'py>, address: u64, size: usize) -> Option<Bound<'py, PyBytes>> {
+        return self.inner.read(address, size).map(|bytes| PyBytes::new_bound(py, bytes));
+    }
+
+    fn __repr__(&self) -> String {
+        return format!("Image({0}, {1} sections, base {2:#x})", self.format(), self.inner.sections.len(), self.inner.base);
+    }
+}
+
+#[pyfunction]
+fn parse_signature(text: &str) -> PyResult<PySignature> {
+    return PySignature::new(text);
+}
+
+#[pyfunction]
+#[pyo3(signature = (signature, dialect_name = "sourcemod"))]
+fn convert(signature: &Bound<'_, PyAny>, dialect_name: &str) -> PyResult<String> {
+    return Ok(dialect(dialect_name)?.format(&signature_arg(signature)?.bytes));
+}
+
+#[pyfunction]
+fn load(path: &str) -> PyResult<PyImage> {
+    return PyImage::load(path);
+}
+
+//  Matches in a raw buffer, reported relative to `base`
+#[pyfunction]
+#[pyo3(signature = (signature, data, base = 0, limit = 50))]
+fn scan_bytes(signature: &Bound<'_, PyAny>, data: &[u8], base: u64, limit: usize) -> PyResult<Vec<u64>> {
+    let signature = signature_arg(signature)?;
+    return Ok(scan_buffer(&signature.bytes, data, base, limit.max(1)));
+}
+
+#[pymodule]
+#[pyo3(name = "smtools_core")]
+fn smtools_core_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PySignature>()?;
+    module.add_class::<PyImage>()?;
+    module.add_function(wrap_pyfunction!(parse_signature, module)?)?;
+    module.add_function(wrap_pyfunction!(convert, module)?)?;
+    module.add_function(wrap_pyfunction!(load, module)?)?;
+    module.add_function(wrap_pyfunction!(scan_bytes, module)?)?;
+    return Ok(());
+}
@@ -0,0 +1,82 @@
+#  Run after `maturin develop`:  python -m unittest discover -s tests
+#  Uses the fixture binaries of the core crate (core/tests/fixtures).
+
+import os
+import unittest
+
+import smtools_core
+
+FIXTURES = os.path.join(os.path.dirname(__file__), "..", "..", "..", "core", "tests", "fixtures")
+
+TARGET = "69 C7 5A 5A 5A 00 03 05 ?? ?? ?? ??"
+CALL = "8B 3D ?? ?? ?? ?? E8 ?? ?? ?? ?? | +7 rel32"
+
+
+def fixture(name):
+    return os.path.join(FIXTURES, name)
+
+
+class SignatureTests(unittest.TestCase):
+    def test_parse(self):
+        sig = smtools_core.parse_signature("\\x48\\x8B\\x2A\\x05 | +1 rel32")
+        self.assertEqual(sig.bytes, [0x48, 0x8B, None, 0x05])
+        self.assertEqual(len(sig), 4)
+        self.assertEqual(sig.wildcards, 1)
+        self.assertEqual(sig.resolve, "+0x1 rel32")
+        self.assertEqual(str(sig), "48 8B ?? 05 | +0x1 rel32")
+
+    def test_convert(self):
+        self.assertEqual(smtools_core.convert("48 ?? 05"), "\\x48\\x2A\\x05")
+        self.assertEqual(smtools_core.convert("48 ?? 05", "mask"), "\\x48\\x00\\x05 x?x")
+        self.assertEqual(smtools_core.Signature("\\x48\\x2A").format(), "48 ??")
+
+    def test_errors(self):
+        with self.assertRaises(ValueError):
+            smtools_core.parse_signature("48 XY")
+        with self.assertRaises(ValueError):
+            smtools_core.convert("48", "nasm")
+
+    def test_scan_bytes(self):
+        data = bytes([0x90, 0x48, 0x8B, 0x05, 0x90, 0x48, 0x8B, 0x06, 0x90, 0x90])
+        self.assertEqual(smtools_core.scan_bytes("48 8B ??", data, base=0x1000), [0x1001, 0x1005])
+        self.assertEqual(smtools_core.scan_bytes("48 8B ??", data, limit=1), [1])
+        self.assertEqual(smtools_core.scan_bytes("AA BB", b"\x00\x00\xAA\xBB\x00"), [2])
+
+
+class ImageTests(unittest.TestCase):
+    def check_image(self, image, base):
+        self.assertEqual(image.scan(TARGET, code_only=True), [base + 0x1000])
+
+        call = smtools_core.Signature(CALL)
+        matches = image.scan(call)
+        self.assertEqual(matches, [base + 0x100D])
+        self.assertEqual(image.resolve(matches[0], call), base + 0x1000)
+        self.assertEqual(image.resolve(matches[0], "8B 3D | +2 rel32"), base + 0x2000)
+        self.assertEqual(image.read(base + 0x2000, 4), b"\x34\x12\x00\x00")
+        self.assertIsNone(image.read(base + 0x2000, 8))
+
+    def test_elf(self):
+        image = smtools_core.load(fixture("sample.elf"))
+        self.assertEqual(image.format, "elf")
+        self.assertEqual(image.platform, "linux64")
+        self.assertTrue(image.is_64)
+        self.assertIn((".text", 0x401000, 0x20, True), image.sections)
+        self.check_image(image, 0x400000)
+
+    def test_pe(self):
+        with open(fixture("sample.exe"), "rb") as data:
+            image = smtools_core.Image.parse(data.read())
+        self.assertEqual(image.format, "pe")
+        self.assertEqual(image.platform, "windows64")
+        self.assertEqual(image.base, 0x140000000)
+        self.check_image(image, 0x140000000)
+
+    def test_load_errors(self):
+        with self.assertRaises(OSError):
+            smtools_core.load(fixture("missing.bin"))
+        with self.assertRaises(ValueError):
+            smtools_core.load(fixture("sample.c"))
+
+
+if __name__ == "__main__":
+    unittest.main()
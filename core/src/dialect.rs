@@ -0,0 +1,56 @@
+use crate::sigbyte::SigByte;
+
+//  How a signature is written out
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Dialect {
+    //  "48 8B ?? ??", as used by IDA and most scanners
+    Generic,
+    //  "\x48\x8B\x2A\x2A", as used in SourceMod gamedata
+    SourceMod,
+    //  "\x48\x8B\x00\x00 xx??", a byte string and a mask
+    Mask,
+}
+
+impl Dialect
+{
+    pub const ALL: [Dialect; 3] = [Dialect::Generic, Dialect::SourceMod, Dialect::Mask];
+
+    //  Key used in settings
+    pub fn name(&self) -> &'static str {
+        match &self {
+            Dialect::Generic => "generic",
+            Dialect::SourceMod => "sourcemod",
+            Dialect::Mask => "mask",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match &self {
+            Dialect::Generic => "Generic",
+            Dialect::SourceMod => "Sourcemod",
+            Dialect::Mask => "Mask",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Dialect> {
+        Dialect::ALL.into_iter().find(|dialect| dialect.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn format(&self, bytes: &[SigByte]) -> String {
+        match &self {
+            Dialect::Generic => bytes.iter().map(|s| s.to_str_generic()).collect::<String>().trim_end().to_owned(),
+            Dialect::SourceMod => bytes.iter().map(|s| s.to_str_sm()).collect::<String>(),
+            Dialect::Mask => {
+                let data = bytes.iter().map(|s| match s {
+                    SigByte::Wildcard => "\\x00".to_owned(),
+                    SigByte::Match(value) => format!("\\x{:02X}", value),
+                }).collect::<String>();
+                let mask = bytes.iter().map(|s| match s {
+                    SigByte::Wildcard => '?',
+                    SigByte::Match(_) => 'x',
+                }).collect::<String>();
+                format!("{0} {1}", data, mask)
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+
+//  Fuzzy scan:
+//  Find near-matches of a signature, allowing a budget of mismatching concrete
+//  bytes and inserted/deleted bytes. Used to find where a broken signature moved.
+//
+//  With k edits allowed, splitting the concrete bytes into k+1 pieces means at least one
+//  piece still matches exactly. Exact piece hits give candidate starts, and only those
+//  are aligned properly.
+
+use std::collections::BTreeSet;
+use crate::sigbyte::SigByte;
+
+#[derive(Copy, Clone, Debug)]
+pub struct FuzzyOptions {
+    pub max_mismatches: usize,
+    pub max_indels: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FuzzyDiff {
+    //  Signature byte `index` expected `expected`, the binary has `found`
+    Mismatch { index: usize, expected: u8, found: u8 },
+    //  The binary has an extra byte before signature byte `index`
+    Inserted { index: usize, found: u8 },
+    //  Signature byte `index` is missing from the binary
+    Deleted { index: usize },
+}
+
+#[derive(Clone, Debug)]
+pub struct FuzzyMatch {
+    //  Buffer offset of the first matched byte
+    pub offset: u64,
+    //  Bytes of the binary covered by the match
+    pub length: usize,
+    pub mismatches: usize,
+    pub indels: usize,
+    //  Out of 100, higher is closer
+    pub score: f64,
+    pub diffs: Vec<FuzzyDiff>,
+}
+
+fn piece_matches(piece: &[SigByte], buffer: &[u8], offset: usize) -> bool {
+    if offset + piece.len() > buffer.len() {
+        return false;
+    }
+
+    piece.iter().enumerate().all(|(index, byte)| match byte {
+        SigByte::Wildcard => true,
+        SigByte::Match(value) => *value == buffer[offset + index],
+    })
+}
+
+fn byte_matches(byte: &SigByte, value: u8) -> bool {
+    match byte {
+        SigByte::Wildcard => true,
+        SigByte::Match(expected) => *expected == value,
+    }
+}
+
+//  Cost of aligning sig[..i] against buffer[start..start+j], as (edits, indels, mismatches)
+type Cell = Option<(usize, usize, usize)>;
+
+//  Banded alignment of the whole signature against the buffer at `start`
+fn align(signature: &[SigByte], buffer: &[u8], start: usize, options: &FuzzyOptions) -> Option<FuzzyMatch> {
+    let n = signature.len();
+    let band = options.max_indels;
+    let width = 2 * band + 1;
+
+    //  table[i][d] covers buffer length j = i + d - band
+    let mut table: Vec<Vec<Cell>> = vec![vec![None; width]; n + 1];
+    let better = |a: Cell, b: Cell| -> Cell {
+        match (a, b) {
+            (None, other) | (other, None) => other,
+            (Some(x), Some(y)) => if (x.0, x.1) <= (y.0, y.1) { Some(x) } else { Some(y) },
+        }
+    };
+
+    table[0][band] = Some((0, 0, 0));
+    for (d, cell) in table[0].iter_mut().enumerate().skip(band + 1) {
+        let j = d - band;
+        *cell = Some((j, j, 0));
+    }
+
+    for i in 1..=n {
+        for d in 0..width {
+            let j = i as i64 + d as i64 - band as i64;
+            if j < 0 || start + (j as usize) > buffer.len() {
+                continue;
+            }
+            let j = j as usize;
+
+            let mut cell: Cell = None;
+
+            //  Diagonal: match or mismatch
+            if j >= 1 {
+                if let Some((edits, indels, mismatches)) = table[i - 1][d] {
+                    if byte_matches(&signature[i - 1], buffer[start + j - 1]) {
+                        cell = better(cell, Some((edits, indels, mismatches)));
+                    }
+                    else {
+                        cell = better(cell, Some((edits + 1, indels, mismatches + 1)));
+                    }
+                }
+            }
+
+            //  Signature byte deleted from the binary
+            if d + 1 < width {
+                if let Some((edits, indels, mismatches)) = table[i - 1][d + 1] {
+                    cell = better(cell, Some((edits + 1, indels + 1, mismatches)));
+                }
+            }
+
+            //  Extra byte inserted in the binary
+            if d >= 1 && j >= 1 {
+                if let Some((edits, indels, mismatches)) = table[i][d - 1] {
+                    cell = better(cell, Some((edits + 1, indels + 1, mismatches)));
+                }
+            }
+
+            if let Some((_, indels, mismatches)) = cell {
+                if indels > options.max_indels || mismatches > options.max_mismatches {
+                    cell = None;
+                }
+            }
+
+            table[i][d] = cell;
+        }
+    }
+
+    //  Best end anywhere in the band
+    let mut best: Option<(usize, Cell)> = None;
+    for (d, cell) in table[n].iter().copied().enumerate() {
+        if cell.is_none() {
+            continue;
+        }
+        best = match best {
+            Some((current, existing)) if better(existing, cell) == existing => Some((current, existing)),
+            _ => Some((d, cell)),
+        };
+    }
+
+    let (end, cell) = best?;
+    let (_, indels, mismatches) = cell?;
+
+    //  Walk back through the table to describe each difference
+    let mut diffs = Vec::new();
+    let (mut i, mut d) = (n, end);
+    while i > 0 || d != band {
+        let j = (i as i64 + d as i64 - band as i64) as usize;
+        let here = table[i][d];
+
+        if i > 0 && j > 0 {
+            if let Some(previous) = table[i - 1][d] {
+                let matched = byte_matches(&signature[i - 1], buffer[start + j - 1]);
+                let expected = if matched { previous } else { (previous.0 + 1, previous.1, previous.2 + 1) };
+                if Some(expected) == here {
+                    if !matched {
+                        if let SigByte::Match(value) = signature[i - 1] {
+                            diffs.push(FuzzyDiff::Mismatch { index: i - 1, expected: value, found: buffer[start + j - 1] });
+                        }
+                    }
+                    i -= 1;
+                    continue;
+                }
+            }
+        }
+
+        if i > 0 && d + 1 < width {
+            if let Some(previous) = table[i - 1][d + 1] {
+                if Some((previous.0 + 1, previous.1 + 1, previous.2)) == here {
+                    diffs.push(FuzzyDiff::Deleted { index: i - 1 });
+                    i -= 1;
+                    d += 1;
+                    continue;
+                }
+            }
+        }
+
+        if d >= 1 && j >= 1 {
+            diffs.push(FuzzyDiff::Inserted { index: i, found: buffer[start + j - 1] });
+            d -= 1;
+            continue;
+        }
+
+        break;
+    }
+    diffs.reverse();
+
+    let concrete = signature.iter().filter(|b| matches!(b, SigByte::Match(_))).count().max(1);
+    let penalty = mismatches as f64 + 1.5 * indels as f64;
+    let score = (100.0 * (1.0 - penalty / concrete as f64)).max(0.0);
+    let length = (n as i64 + end as i64 - band as i64) as usize;
+
+    Some(FuzzyMatch { offset: start as u64, length, mismatches, indels, score, diffs })
+}
+
+pub fn fuzzy_scan(signature: &[SigByte], buffer: &[u8], options: &FuzzyOptions, max_results: usize) -> Vec<FuzzyMatch> {
+    let edits = options.max_mismatches + options.max_indels;
+    let n = signature.len();
+
+    if n == 0 || buffer.len() < n {
+        return Vec::new();
+    }
+
+    //  Split the concrete bytes into edits+1 pieces, at least one of which must match exactly.
+    //  Wildcards can't miss, so pieces made of them would not narrow anything down.
+    let concrete: Vec<usize> = signature.iter().enumerate()
+        .filter(|(_, b)| matches!(b, SigByte::Match(_)))
+        .map(|(index, _)| index)
+        .collect();
+    if concrete.is_empty() {
+        return Vec::new();
+    }
+
+    let pieces = (edits + 1).min(concrete.len());
+    let piece_len = concrete.len() / pieces;
+    let mut starts: BTreeSet<usize> = BTreeSet::new();
+
+    for piece in 0..pieces {
+        let first = piece * piece_len;
+        let last = if piece == pieces - 1 { concrete.len() } else { first + piece_len };
+        let begin = concrete[first];
+        let slice = &signature[begin..(concrete[last - 1] + 1)];
+
+        for offset in 0..buffer.len() {
+            if !piece_matches(slice, buffer, offset) {
+                continue;
+            }
+
+            let base = offset as i64 - begin as i64;
+            for shift in -(options.max_indels as i64)..=(options.max_indels as i64) {
+                let start = base + shift;
+                if start >= 0 && (start as usize) < buffer.len() {
+                    starts.insert(start as usize);
+                }
+            }
+        }
+    }
+
+    let mut found: Vec<FuzzyMatch> = starts.into_iter()
+        .filter_map(|start| align(signature, buffer, start, options))
+        .collect();
+
+    found.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal).then(a.offset.cmp(&b.offset)));
+
+    //  With indels, neighbouring starts describe the same location, so keep the best of each
+    let mut ranked: Vec<FuzzyMatch> = Vec::new();
+    for candidate in found.into_iter() {
+        let overlaps = ranked.iter().any(|r| r.offset.abs_diff(candidate.offset) <= options.max_indels as u64);
+        if !overlaps {
+            ranked.push(candidate);
+        }
+        if ranked.len() >= max_results {
+            break;
+        }
+    }
+
+    ranked
+}
+
+impl FuzzyDiff
+{
+    pub fn describe(&self) -> String {
+        match &self {
+            FuzzyDiff::Mismatch { index, expected, found } => format!("+{0:#x}: expected {1:02X}, found {2:02X}", index, expected, found),
+            FuzzyDiff::Inserted { index, found } => format!("+{0:#x}: extra byte {1:02X}", index, found),
+            FuzzyDiff::Deleted { index } => format!("+{0:#x}: byte missing", index),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+
+//  SMTools core:
+//  The parts of SMTools that only need bytes: signatures, their dialects, scanning
+//  and a small ELF/PE loader to scan files on disk.
+
+pub mod dialect;
+pub mod fuzzy;
+pub mod loader;
+pub mod scan;
+pub mod sigbyte;
+pub mod signature;
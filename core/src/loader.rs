@@ -0,0 +1,172 @@
+
+//  Loader:
+//  Just enough of ELF and PE to scan a file on disk: every section with file data,
+//  placed at its virtual address, and pointer reads for resolve chains.
+
+use std::fs;
+use std::path::Path;
+use object::{BinaryFormat, Object, ObjectSection, SectionKind};
+use crate::scan::scan_buffer;
+use crate::sigbyte::SigByte;
+use crate::signature::ResolveStep;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ImageFormat {
+    Elf,
+    Pe,
+}
+
+pub struct Section {
+    pub name: String,
+    pub address: u64,
+    pub data: Vec<u8>,
+    pub executable: bool,
+}
+
+pub struct Image {
+    pub format: ImageFormat,
+    pub is_64: bool,
+    //  Address the image is linked at
+    pub base: u64,
+    pub sections: Vec<Section>,
+}
+
+impl ImageFormat
+{
+    //  Gamedata platform key, without the 64-bit suffix
+    pub fn os(&self) -> &'static str {
+        match &self {
+            ImageFormat::Elf => "linux",
+            ImageFormat::Pe => "windows",
+        }
+    }
+}
+
+impl Image
+{
+    //  Gamedata platform key, eg. "linux" or "windows64"
+    pub fn platform(&self) -> String {
+        match self.is_64 {
+            true => format!("{0}64", self.format.os()),
+            false => self.format.os().to_owned(),
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Image, String> {
+        let file = object::File::parse(data).map_err(|err| format!("Failed to parse: {0}", err))?;
+
+        let format = match file.format() {
+            BinaryFormat::Elf => ImageFormat::Elf,
+            BinaryFormat::Pe => ImageFormat::Pe,
+            other => return Err(format!("Unsupported format {0:?}", other)),
+        };
+
+        let mut sections = Vec::new();
+        for section in file.sections() {
+            //  .bss and friends have an address but nothing to scan
+            let data = match section.data() {
+                Ok(data) if !data.is_empty() => data,
+                _ => continue,
+            };
+
+            //  ELF sections that are not loaded (.symtab, .comment...) have no address
+            if section.address() == 0 && format == ImageFormat::Elf {
+                continue;
+            }
+
+            sections.push(Section {
+                name: section.name().unwrap_or("").to_owned(),
+                address: section.address(),
+                data: data.to_vec(),
+                executable: section.kind() == SectionKind::Text,
+            });
+        }
+
+        Ok(Image { format, is_64: file.is_64(), base: file.relative_address_base(), sections })
+    }
+
+    pub fn pointer_size(&self) -> usize {
+        if self.is_64 { 8 } else { 4 }
+    }
+
+    //  `len` bytes at `address`, if a single section holds all of them
+    pub fn read(&self, address: u64, len: usize) -> Option<&[u8]> {
+        for section in self.sections.iter() {
+            if address < section.address {
+                continue;
+            }
+
+            let offset = (address - section.address) as usize;
+            if offset < section.data.len() && section.data.len() - offset >= len {
+                return Some(&section.data[offset..offset + len]);
+            }
+        }
+
+        None
+    }
+
+    //  Matches of a signature in every section (or only executable ones), as addresses
+    pub fn scan(&self, signature: &[SigByte], code_only: bool, maxmatches: usize) -> Vec<u64> {
+        let mut matches = Vec::new();
+
+        for section in self.sections.iter().filter(|s| s.executable || !code_only) {
+            if matches.len() >= maxmatches {
+                break;
+            }
+
+            matches.extend(scan_buffer(signature, &section.data, section.address, maxmatches - matches.len()));
+        }
+
+        matches
+    }
+
+    fn read_pointer(&self, address: u64) -> Result<u64, String> {
+        let size = self.pointer_size();
+        let bytes = self.read(address, size).ok_or(format!("Failed to read pointer at {0:#08X}", address))?;
+
+        let mut value: u64 = 0;
+        for (index, byte) in bytes.iter().enumerate() {
+            value |= (*byte as u64) << (index * 8);
+        }
+
+        Ok(value)
+    }
+
+    //  Walk a resolve chain from a match, returning the cursor after every step.
+    //  The last entry is the resolved target.
+    pub fn resolve(&self, address: u64, steps: &[ResolveStep]) -> Result<Vec<u64>, String> {
+        let mut hops = Vec::new();
+        let mut cursor = address;
+
+        for step in steps.iter() {
+            cursor = match step {
+                ResolveStep::Offset(delta) => cursor.checked_add_signed(*delta)
+                    .ok_or(format!("Offset {0} overflows at {1:#08X}", step.to_str(), cursor))?,
+                ResolveStep::Rel32 => {
+                    let bytes = self.read(cursor, 4).ok_or(format!("Failed to read rel32 at {0:#08X}", cursor))?;
+                    let rel = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    cursor.checked_add(4).and_then(|end| end.checked_add_signed(rel as i64))
+                        .ok_or(format!("rel32 at {0:#08X} overflows", cursor))?
+                }
+                ResolveStep::Deref(count) => {
+                    let mut value = cursor;
+                    for _ in 0..*count {
+                        value = self.read_pointer(value)?;
+                    }
+                    value
+                }
+            };
+
+            hops.push(cursor);
+        }
+
+        Ok(hops)
+    }
+}
+
+pub fn load_image(path: &Path) -> Result<Image, String> {
+    let data = fs::read(path)
+        .map_err(|err| format!("Failed to read {0}: {1}", path.display(), err))?;
+
+    Image::parse(&data)
+}
@@ -0,0 +1,102 @@
+use crate::sigbyte::SigByte;
+
+pub fn sig_matches(signature: &[SigByte], buffer: &[u8], offset: u64) -> bool {
+    let offset = match usize::try_from(offset) {
+        Ok(offset) => offset,
+        Err(_) => return false,
+    };
+
+    match offset.checked_add(signature.len()) {
+        Some(end) if end <= buffer.len() => {}
+        _ => return false,
+    }
+
+    for index in 0..signature.len() {
+        let mode = signature[index];
+        let byte = buffer[index + offset];
+
+        let good = match mode {
+            SigByte::Wildcard => true,
+            SigByte::Match(value) => value == byte,
+        };
+
+        if !good {
+            return false;
+        }
+    }
+
+    true
+}
+
+//  Every buffer offset where the signature matches with at most `max_mismatches`
+//  concrete bytes differing, along with the indices of the bytes that differ
+pub fn near_matches(signature: &[SigByte], buffer: &[u8], max_mismatches: usize) -> Vec<(u64, Vec<usize>)> {
+    let mut found = Vec::new();
+
+    if signature.is_empty() || buffer.len() < signature.len() {
+        return found;
+    }
+
+    for address in 0..=(buffer.len() - signature.len()) {
+        let mut mismatches = Vec::new();
+
+        for index in 0..signature.len() {
+            if let SigByte::Match(value) = signature[index] {
+                if value != buffer[address + index] {
+                    mismatches.push(index);
+                    if mismatches.len() > max_mismatches {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if mismatches.len() <= max_mismatches {
+            found.push((address as u64, mismatches));
+        }
+    }
+
+    found
+}
+
+//  Fewest concrete bytes in which any position other than `target` differs
+//  from the signature, up to `cap`. This is how close the signature is to colliding.
+pub fn hamming_margin(signature: &[SigByte], buffer: &[u8], target: u64, cap: usize) -> usize {
+    let mut margin = cap;
+
+    if cap == 0 {
+        return 0;
+    }
+
+    for (address, mismatches) in near_matches(signature, buffer, cap - 1).into_iter() {
+        if address != target {
+            margin = margin.min(mismatches.len());
+        }
+    }
+
+    margin
+}
+
+//  Every match in `buf`, reported as addresses relative to `start`
+pub fn scan_buffer(signature: &[SigByte], buf: &[u8], start: u64, maxmatches: usize) -> Vec<u64> {
+    let mut matches = Vec::new();
+
+    if buf.len() < signature.len() {
+        return matches;
+    }
+
+    for address in 0..=(buf.len() - signature.len()) as u64 {
+        let real_address = address + start;
+        if sig_matches(signature, buf, address) {
+            matches.push(real_address);
+
+            if matches.len() >= maxmatches  {
+                //  Cut search short early
+                return matches;
+            }
+        }
+    }
+
+
+    matches
+}
@@ -0,0 +1,146 @@
+use crate::sigbyte::{parse_signature, SigByte};
+
+//  A single step taken after a signature matches to reach the real target.
+//  Steps are applied in order to a cursor that starts at the match address.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ResolveStep {
+    //  Move the cursor by a fixed amount
+    Offset(i64),
+    //  Read a rel32 at the cursor and follow it, relative to the end of the rel32
+    Rel32,
+    //  Read a pointer-sized address at the cursor, N times (eg. an imm32/imm64 operand)
+    Deref(u32),
+}
+
+//  A value read out of the matched bytes at runtime, eg. a struct member displacement
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Capture {
+    //  Offset of the value from the start of the signature
+    pub offset: usize,
+    pub width: usize,
+    //  Value in the current binary
+    pub value: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Signature {
+    pub bytes: Vec<SigByte>,
+    pub resolve: Vec<ResolveStep>,
+    pub capture: Option<Capture>,
+}
+
+impl Signature
+{
+    pub fn new(bytes: Vec<SigByte>) -> Signature {
+        Signature { bytes, resolve: Vec::new(), capture: None }
+    }
+
+    pub fn with_resolve(bytes: Vec<SigByte>, resolve: Vec<ResolveStep>) -> Signature {
+        Signature { bytes, resolve, capture: None }
+    }
+
+    pub fn to_str_generic(&self) -> String {
+        self.bytes.iter().map(|s| s.to_str_generic()).collect::<String>()
+    }
+
+    pub fn to_str_sm(&self) -> String {
+        self.bytes.iter().map(|s| s.to_str_sm()).collect::<String>()
+    }
+
+    pub fn to_str_resolve(&self) -> String {
+        self.resolve.iter().map(|s| s.to_str()).collect::<Vec<String>>().join(" ")
+    }
+
+    //  Generic bytes plus the resolve chain, in the form parse_full_signature reads back
+    pub fn to_str_full(&self) -> String {
+        let bytes = self.to_str_generic().trim_end().to_owned();
+        if self.resolve.is_empty() {
+            return bytes;
+        }
+        format!("{0} | {1}", bytes, self.to_str_resolve())
+    }
+}
+
+impl ResolveStep
+{
+    pub fn describe(&self) -> String {
+        match &self {
+            ResolveStep::Offset(delta) if *delta < 0 => format!("Offset by -{0:#x}", delta.unsigned_abs()),
+            ResolveStep::Offset(delta) => format!("Offset by +{0:#x}", delta),
+            ResolveStep::Rel32 => "Follow rel32".to_owned(),
+            ResolveStep::Deref(count) => format!("Dereference x{0}", count),
+        }
+    }
+
+    pub fn to_str(&self) -> String {
+        match &self {
+            ResolveStep::Offset(delta) if *delta < 0 => format!("-{0:#x}", delta.unsigned_abs()),
+            ResolveStep::Offset(delta) => format!("+{0:#x}", delta),
+            ResolveStep::Rel32 => "rel32".to_owned(),
+            ResolveStep::Deref(1) => "deref".to_owned(),
+            ResolveStep::Deref(count) => format!("deref*{0}", count),
+        }
+    }
+}
+
+fn parse_number(string: &str) -> Result<i64, String> {
+    let parsed = match string.strip_prefix("0x").or(string.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16),
+        None => string.parse::<i64>(),
+    };
+
+    parsed.map_err(|err| format!("Failed to parse {0}: {1}", string, err))
+}
+
+//  Parse a resolve chain such as "+0x1 rel32 deref*2"
+pub fn parse_resolve(string: &str) -> Result<Vec<ResolveStep>, String>
+{
+    let mut steps: Vec<ResolveStep> = Vec::new();
+
+    for token in string.split_whitespace() {
+        let lowered = token.to_lowercase();
+
+        let step = match lowered.as_str()
+        {
+            "rel32" => ResolveStep::Rel32,
+            //  "ptr" was written by older versions for a single dereference
+            "deref" | "ptr" => ResolveStep::Deref(1),
+            _ => {
+                if let Some(count) = lowered.strip_prefix("deref*") {
+                    match u32::try_from(parse_number(count)?) {
+                        Ok(parsed) if parsed > 0 => ResolveStep::Deref(parsed),
+                        _ => return Err(format!("Invalid dereference count in {0}", token)),
+                    }
+                }
+                else if let Some(delta) = lowered.strip_prefix('+') {
+                    ResolveStep::Offset(parse_number(delta)?)
+                }
+                else if let Some(delta) = lowered.strip_prefix('-') {
+                    let delta = parse_number(delta)?.checked_neg().ok_or(format!("Offset out of range in {0}", token))?;
+                    ResolveStep::Offset(delta)
+                }
+                else {
+                    return Err(format!("Unknown resolve step {0}", token));
+                }
+            }
+        };
+
+        steps.push(step);
+    }
+
+    Ok(steps)
+}
+
+//  Parse a signature with an optional resolve chain, eg. "E8 ?? ?? ?? ?? 8B | +1 rel32"
+pub fn parse_full_signature(string: String) -> Result<Signature, String>
+{
+    let (bytes, chain) = match string.split_once('|') {
+        Some((bytes, chain)) => (bytes.to_owned(), chain.to_owned()),
+        None => (string, String::new()),
+    };
+
+    let bytes = parse_signature(bytes)?;
+    let resolve = parse_resolve(&chain)?;
+
+    Ok(Signature::with_resolve(bytes, resolve))
+}
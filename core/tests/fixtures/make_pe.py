@@ -0,0 +1,38 @@
+#  Builds sample.exe, a minimal PE32+ with the same .text and .data as sample.elf,
+#  linked at 0x140000000 so the sections land at 0x140001000 and 0x140002000.
+
+import struct
+
+TEXT = bytes.fromhex(
+    "69c75a5a5a00"      # imul eax, edi, 0x5a5a5a
+    "0305f40f0000"      # add eax, [rip+0xff4]          ; counter
+    "c3"                # ret
+    "8b3ded0f0000"      # mov edi, [rip+0xfed]          ; counter
+    "e8e8ffffff"        # call target
+    "8905e20f0000"      # mov [rip+0xfe2], eax          ; counter
+    "ebfe"              # jmp $
+    "90909090"
+)
+DATA = struct.pack("<I", 0x1234)
+
+
+def section(name, vsize, va, raw, pointer, characteristics):
+    return struct.pack("<8sIIIIIIHHI", name, vsize, va, raw, pointer, 0, 0, 0, 0, characteristics)
+
+
+dos = b"MZ" + b"\0" * 0x3A + struct.pack("<I", 0x40)
+coff = struct.pack("<HHIIIHH", 0x8664, 2, 0, 0, 0, 240, 0x22)
+optional = struct.pack("<HBBIIIII", 0x20B, 14, 0, 0x200, 0x200, 0, 0x100D, 0x1000)
+optional += struct.pack("<QIIHHHHHHIIIIHHQQQQII", 0x140000000, 0x1000, 0x200, 6, 0, 0, 0, 6, 0, 0,
+                        0x3000, 0x200, 0, 3, 0, 0x100000, 0x1000, 0x100000, 0x1000, 0, 16)
+optional += b"\0" * (16 * 8)
+assert len(optional) == 240
+
+headers = dos + b"PE\0\0" + coff + optional
+headers += section(b".text", len(TEXT), 0x1000, 0x200, 0x200, 0x60000020)
+headers += section(b".data", len(DATA), 0x2000, 0x200, 0x400, 0xC0000040)
+
+image = headers.ljust(0x200, b"\0") + TEXT.ljust(0x200, b"\0") + DATA.ljust(0x200, b"\0")
+
+with open("sample.exe", "wb") as output:
+    output.write(image)
@@ -0,0 +1,15 @@
+//  Fixture for the loader and scanner tests, built with:
+//  gcc -O1 -nostdlib -static -no-pie -fno-asynchronous-unwind-tables -o sample.elf sample.c
+
+int counter = 0x1234;
+
+__attribute__((noinline)) int target(int value)
+{
+    return value * 0x5A5A5A + counter;
+}
+
+void _start(void)
+{
+    counter = target(counter);
+    for (;;);
+}
@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+use smtools_core::loader::{load_image, ImageFormat};
+use smtools_core::sigbyte::parse_signature;
+use smtools_core::signature::parse_full_signature;
+
+//  sample.elf is built from fixtures/sample.c, sample.exe by fixtures/make_pe.py.
+//  Both hold the same code: `target` at .text+0, `_start` at .text+0xd, `counter` at .data+0.
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+#[test]
+fn loads_elf() {
+    let image = load_image(&fixture("sample.elf")).unwrap();
+
+    assert_eq!(image.format, ImageFormat::Elf);
+    assert_eq!(image.platform(), "linux64");
+    assert!(image.is_64);
+
+    let text = image.sections.iter().find(|s| s.name == ".text").unwrap();
+    assert_eq!(text.address, 0x401000);
+    assert!(text.executable);
+    assert!(!image.sections.iter().any(|s| s.name == ".symtab"));
+}
+
+#[test]
+fn loads_pe() {
+    let image = load_image(&fixture("sample.exe")).unwrap();
+
+    assert_eq!(image.format, ImageFormat::Pe);
+    assert_eq!(image.platform(), "windows64");
+    assert_eq!(image.base, 0x140000000);
+
+    let text = image.sections.iter().find(|s| s.name == ".text").unwrap();
+    assert_eq!(text.address, 0x140001000);
+    assert!(text.executable);
+}
+
+#[test]
+fn scans_and_resolves_both_formats() {
+    for (name, base) in [("sample.elf", 0x400000u64), ("sample.exe", 0x140000000u64)] {
+        let image = load_image(&fixture(name)).unwrap();
+
+        let target = parse_signature("69 C7 5A 5A 5A 00 03 05 ?? ?? ?? ??".to_owned()).unwrap();
+        assert_eq!(image.scan(&target, true, 10), vec![base + 0x1000], "{0}", name);
+
+        //  The call in _start leads back to target, the load before it to counter
+        let call = parse_full_signature("8B 3D ?? ?? ?? ?? E8 ?? ?? ?? ?? | +7 rel32".to_owned()).unwrap();
+        let matches = image.scan(&call.bytes, true, 10);
+        assert_eq!(matches, vec![base + 0x100d], "{0}", name);
+        assert_eq!(image.resolve(matches[0], &call.resolve).unwrap(), vec![base + 0x1014, base + 0x1000]);
+
+        let global = parse_full_signature("8B 3D ?? ?? ?? ?? E8 | +2 rel32".to_owned()).unwrap();
+        assert_eq!(image.resolve(matches[0], &global.resolve).unwrap().last(), Some(&(base + 0x2000)));
+        assert_eq!(image.read(base + 0x2000, 4), Some(&[0x34, 0x12, 0x00, 0x00][..]));
+
+        //  counter is 4 bytes, a pointer read runs off the end of .data
+        let pointer = parse_full_signature("8B 3D | +2 rel32 deref".to_owned()).unwrap();
+        assert!(image.resolve(matches[0], &pointer.resolve).is_err());
+
+        let wrapped = parse_full_signature("8B 3D | -0x7fffffffffffffff -0x7fffffffffffffff".to_owned()).unwrap();
+        assert!(image.resolve(matches[0], &wrapped.resolve).is_err());
+    }
+}
+
+#[test]
+fn code_only_skips_data() {
+    let image = load_image(&fixture("sample.elf")).unwrap();
+    let counter = parse_signature("34 12".to_owned()).unwrap();
+
+    assert_eq!(image.scan(&counter, true, 10), Vec::<u64>::new());
+}
+
+#[test]
+fn rejects_non_images() {
+    assert!(load_image(&fixture("sample.c")).is_err());
+    assert!(load_image(&fixture("missing.bin")).is_err());
+}
@@ -0,0 +1,99 @@
+use smtools_core::dialect::Dialect;
+use smtools_core::fuzzy::{fuzzy_scan, FuzzyDiff, FuzzyOptions};
+use smtools_core::scan::{scan_buffer, sig_matches};
+use smtools_core::sigbyte::{parse_signature, SigByte};
+use smtools_core::signature::{parse_full_signature, ResolveStep};
+
+#[test]
+fn parses_generic_and_sourcemod() {
+    let generic = parse_signature("48 8B ?? ? 05".to_owned()).unwrap();
+    let sourcemod = parse_signature("\\x48\\x8B\\x2A\\x2A\\x05".to_owned()).unwrap();
+
+    assert_eq!(generic, vec![SigByte::Match(0x48), SigByte::Match(0x8B), SigByte::Wildcard, SigByte::Wildcard, SigByte::Match(0x05)]);
+    assert_eq!(generic, sourcemod);
+    assert_eq!(parse_signature("\\x48\\x8b\\x2a\\x2a\\x05".to_owned()).unwrap(), sourcemod);
+}
+
+#[test]
+fn rejects_bad_bytes() {
+    assert!(parse_signature("48 XY".to_owned()).is_err());
+    assert!(parse_full_signature("48 | jump".to_owned()).is_err());
+}
+
+#[test]
+fn formats_every_dialect() {
+    let bytes = parse_signature("48 ?? 05".to_owned()).unwrap();
+
+    assert_eq!(Dialect::Generic.format(&bytes), "48 ?? 05");
+    assert_eq!(Dialect::SourceMod.format(&bytes), "\\x48\\x2A\\x05");
+    assert_eq!(Dialect::Mask.format(&bytes), "\\x48\\x00\\x05 x?x");
+    assert_eq!(Dialect::from_name("SourceMod"), Some(Dialect::SourceMod));
+}
+
+#[test]
+fn resolve_chain_round_trips() {
+    let signature = parse_full_signature("E8 ?? ?? ?? ?? | +0x1 rel32 deref*2 -4 ptr".to_owned()).unwrap();
+
+    assert_eq!(signature.resolve, vec![
+        ResolveStep::Offset(1), ResolveStep::Rel32, ResolveStep::Deref(2), ResolveStep::Offset(-4), ResolveStep::Deref(1),
+    ]);
+    assert_eq!(signature.to_str_resolve(), "+0x1 rel32 deref*2 -0x4 deref");
+    assert_eq!(parse_full_signature(signature.to_str_full()).unwrap().resolve, signature.resolve);
+}
+
+#[test]
+fn rejects_bad_resolve_steps() {
+    assert!(parse_full_signature("E8 | deref*0".to_owned()).is_err());
+    assert!(parse_full_signature("E8 | deref*0x100000000".to_owned()).is_err());
+    assert!(parse_full_signature("E8 | deref*-1".to_owned()).is_err());
+}
+
+#[test]
+fn scans_buffers() {
+    let buf = vec![0x90, 0x48, 0x8B, 0x05, 0x90, 0x48, 0x8B, 0x06, 0x90, 0x90];
+    let signature = parse_signature("48 8B ??".to_owned()).unwrap();
+
+    assert_eq!(scan_buffer(&signature, &buf, 0x1000, 10), vec![0x1001, 0x1005]);
+    assert_eq!(scan_buffer(&signature, &buf, 0x1000, 1), vec![0x1001]);
+}
+
+#[test]
+fn scans_up_to_the_last_byte() {
+    let signature = parse_signature("AA BB".to_owned()).unwrap();
+
+    assert_eq!(scan_buffer(&signature, &[0x00, 0x00, 0xAA, 0xBB, 0x00], 0, 10), vec![2]);
+    assert_eq!(scan_buffer(&signature, &[0x00, 0x00, 0xAA, 0xBB], 0, 10), vec![2]);
+    assert_eq!(scan_buffer(&signature, &[0xAA, 0xBB], 0, 10), vec![0]);
+    assert_eq!(scan_buffer(&signature, &[0xAA], 0, 10), Vec::<u64>::new());
+    assert!(!sig_matches(&signature, &[0xAA, 0xBB], 2));
+    assert!(!sig_matches(&signature, &[0xAA, 0xBB], u64::MAX));
+}
+
+#[test]
+fn fuzzy_finds_a_changed_byte() {
+    let buf = vec![0x90, 0x90, 0x55, 0x48, 0x89, 0xE6, 0x41, 0x57, 0x90, 0x90, 0x90, 0x90];
+    let signature = parse_signature("55 48 89 E5 41 57".to_owned()).unwrap();
+
+    let matches = fuzzy_scan(&signature, &buf, &FuzzyOptions { max_mismatches: 1, max_indels: 0 }, 5);
+
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].offset, 2);
+    assert_eq!(matches[0].diffs, vec![FuzzyDiff::Mismatch { index: 3, expected: 0xE5, found: 0xE6 }]);
+}
+
+#[test]
+fn fuzzy_pieces_ignore_wildcard_runs() {
+    //  Split by position, the middle piece would be all wildcards and both others broken
+    let signature = parse_signature("55 48 89 E5 ?? ?? ?? ?? ?? ?? ?? ?? ?? ?? ?? ?? 41 57 41 56".to_owned()).unwrap();
+    let mut buf = vec![0x90; 4];
+    buf.extend([0x54, 0x48, 0x89, 0xE5]);
+    buf.extend([0xCC; 12]);
+    buf.extend([0x41, 0x57, 0x41, 0x55]);
+    buf.extend([0x90; 4]);
+
+    let matches = fuzzy_scan(&signature, &buf, &FuzzyOptions { max_mismatches: 2, max_indels: 0 }, 5);
+
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].offset, 4);
+    assert_eq!(matches[0].mismatches, 2);
+}
@@ -0,0 +1,110 @@
+#  SMTools scripting wrapper
+#  Thin ctypes binding over the C API exported by the SMTools plugin (src/api.rs).
+#  Copy this file next to the plugin (or anywhere on sys.path) and use it from
+#  Binary Ninja's Python console or a headless script:
+#
+#      import smtools
+#      sig = smtools.generate(bv, here)
+#      smtools.scan(bv, sig)
+#      smtools.verify_gamedata(bv, "/path/to/gamedata.txt")
+
+import ctypes
+import json
+import os
+import sys
+
+API_VERSION = 1
+
+
+class SMToolsError(Exception):
+    pass
+
+
+def _library_name():
+    if sys.platform == "win32":
+        return "SMTools.dll"
+    if sys.platform == "darwin":
+        return "libSMTools.dylib"
+    return "libSMTools.so"
+
+
+def _load():
+    candidates = [os.path.dirname(os.path.abspath(__file__))]
+    try:
+        import binaryninja
+        candidates.append(binaryninja.user_plugin_path())
+    except ImportError:
+        pass
+
+    for directory in candidates:
+        path = os.path.join(directory, _library_name())
+        if os.path.exists(path):
+            return ctypes.CDLL(path)
+    raise SMToolsError("Could not find %s in %s" % (_library_name(), ", ".join(candidates)))
+
+
+_lib = _load()
+
+_lib.smtools_api_version.restype = ctypes.c_uint32
+_lib.smtools_last_error.restype = ctypes.c_char_p
+_lib.smtools_free_string.argtypes = [ctypes.c_void_p]
+_lib.smtools_parse.argtypes = [ctypes.c_char_p]
+_lib.smtools_parse.restype = ctypes.c_void_p
+_lib.smtools_format.argtypes = [ctypes.c_char_p, ctypes.c_char_p]
+_lib.smtools_format.restype = ctypes.c_void_p
+_lib.smtools_scan.argtypes = [ctypes.c_void_p, ctypes.c_char_p, ctypes.c_bool, ctypes.POINTER(ctypes.c_uint64), ctypes.c_size_t]
+_lib.smtools_scan.restype = ctypes.c_int64
+_lib.smtools_generate.argtypes = [ctypes.c_void_p, ctypes.c_uint64]
+_lib.smtools_generate.restype = ctypes.c_void_p
+_lib.smtools_verify_gamedata.argtypes = [ctypes.c_void_p, ctypes.c_char_p]
+_lib.smtools_verify_gamedata.restype = ctypes.c_void_p
+
+if _lib.smtools_api_version() != API_VERSION:
+    raise SMToolsError("Plugin API version %d, wrapper expects %d" % (_lib.smtools_api_version(), API_VERSION))
+
+
+def _error():
+    reason = _lib.smtools_last_error()
+    return SMToolsError(reason.decode("utf-8") if reason else "Unknown error")
+
+
+def _take_string(pointer):
+    if not pointer:
+        raise _error()
+    try:
+        return ctypes.cast(pointer, ctypes.c_char_p).value.decode("utf-8")
+    finally:
+        _lib.smtools_free_string(pointer)
+
+
+def _handle(view):
+    return ctypes.cast(view.handle, ctypes.c_void_p)
+
+
+def parse(signature):
+    """Parse a signature in any format; returns its length, wildcard count, every dialect and resolve chain."""
+    return json.loads(_take_string(_lib.smtools_parse(signature.encode("utf-8"))))
+
+
+def format(signature, dialect="sourcemod"):
+    """Convert a signature to "generic", "sourcemod" or "mask"."""
+    return _take_string(_lib.smtools_format(signature.encode("utf-8"), dialect.encode("utf-8")))
+
+
+def scan(view, signature, limit=50, resolve=False):
+    """Addresses where the signature matches, or where each match resolves to with resolve=True."""
+    out = (ctypes.c_uint64 * limit)()
+    count = _lib.smtools_scan(_handle(view), signature.encode("utf-8"), resolve, out, limit)
+    if count < 0:
+        raise _error()
+    return list(out[:count])
+
+
+def generate(view, address):
+    """Generate a signature at address using the view's SMTools settings."""
+    return _take_string(_lib.smtools_generate(_handle(view), address))
+
+
+def verify_gamedata(view, path):
+    """Verify every signature of a gamedata file for this view; returns [{"name", "ok", "status"}]."""
+    return json.loads(_take_string(_lib.smtools_verify_gamedata(_handle(view), path.encode("utf-8"))))
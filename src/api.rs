@@ -0,0 +1,200 @@
+
+//  API:
+//  Exported C functions for scripts running inside Binary Ninja, see python/smtools.py.
+//  Strings are UTF-8. Strings returned by SMTools belong to the caller and are freed with
+//  smtools_free_string. Failures return null (or -1) and leave a reason in smtools_last_error.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use binaryninja::binaryninjacore_sys::BNBinaryView;
+use binaryninja::binaryview::{BinaryView, BinaryViewBase};
+use binaryninja::rc::Ref;
+use crate::gamedata::link::verify_gamedata_file;
+use crate::monkey::binaryview::{create_bv_from_monkey, MonkeyBinaryView};
+use crate::settings::generate_options;
+use crate::signatures::dialect::Dialect;
+use crate::signatures::linear_generate::linear_generate_signature_with;
+use crate::signatures::scan::{find_signature, resolve_match};
+use crate::signatures::sigbyte::SigByte;
+use crate::signatures::signature::{parse_full_signature, Signature};
+use crate::signatures::store::VerifyStatus;
+use crate::utils::function::{find_address_base, read_view};
+
+//  Bumped whenever an exported signature changes
+pub const API_VERSION: u32 = 1;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+fn set_error(reason: &str) {
+    let reason = CString::new(reason.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(reason));
+}
+
+fn clear_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+//  Run `body`, turning errors and panics into a recorded error and `failed`.
+//  Panics must not unwind into the caller's C frames.
+fn guard<T>(failed: T, body: impl FnOnce() -> Result<T, String>) -> T {
+    clear_error();
+
+    return match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(reason)) => {
+            set_error(&reason);
+            failed
+        }
+        Err(_) => {
+            set_error("SMTools panicked, see the log");
+            failed
+        }
+    };
+}
+
+fn into_c_string(text: String) -> Result<*mut c_char, String> {
+    return CString::new(text)
+        .map(|text| text.into_raw())
+        .map_err(|_| "Result contains a NUL byte".to_string());
+}
+
+unsafe fn read_str<'a>(text: *const c_char, what: &str) -> Result<&'a str, String> {
+    if text.is_null() {
+        return Err(format!("{0} is null", what));
+    }
+
+    return CStr::from_ptr(text).to_str().map_err(|_| format!("{0} is not UTF-8", what));
+}
+
+unsafe fn view_from_handle(handle: *mut BNBinaryView) -> Result<Ref<BinaryView>, String> {
+    if handle.is_null() {
+        return Err("View is null".to_string());
+    }
+
+    let monkey = MonkeyBinaryView { handle };
+    return Ok(create_bv_from_monkey(&monkey).to_owned());
+}
+
+#[no_mangle]
+pub extern "C" fn smtools_api_version() -> u32 {
+    return API_VERSION;
+}
+
+//  Reason the last call on this thread failed, or null. Owned by SMTools, valid until the next call.
+#[no_mangle]
+pub extern "C" fn smtools_last_error() -> *const c_char {
+    return LAST_ERROR.with(|last| match last.borrow().as_ref() {
+        Some(reason) => reason.as_ptr(),
+        None => std::ptr::null(),
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn smtools_free_string(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+//  Parse a signature in any supported format (and an optional resolve chain).
+//  Returns JSON: {"length", "wildcards", "generic", "sourcemod", "mask", "resolve"}
+#[no_mangle]
+pub unsafe extern "C" fn smtools_parse(text: *const c_char) -> *mut c_char {
+    return guard(std::ptr::null_mut(), || {
+        let signature = parse_full_signature(read_str(text, "Signature")?.to_owned())?;
+
+        let mut value = serde_json::json!({
+            "length": signature.bytes.len(),
+            "wildcards": signature.bytes.iter().filter(|b| **b == SigByte::Wildcard).count(),
+            "resolve": signature.to_str_resolve(),
+        });
+        for dialect in Dialect::ALL.iter() {
+            value[dialect.name()] = serde_json::Value::String(dialect.format(&signature.bytes));
+        }
+
+        return into_c_string(value.to_string());
+    });
+}
+
+//  Convert a signature to one dialect ("generic", "sourcemod" or "mask")
+#[no_mangle]
+pub unsafe extern "C" fn smtools_format(text: *const c_char, dialect: *const c_char) -> *mut c_char {
+    return guard(std::ptr::null_mut(), || {
+        let signature = parse_full_signature(read_str(text, "Signature")?.to_owned())?;
+        let name = read_str(dialect, "Dialect")?;
+        let dialect = Dialect::from_name(name).ok_or(format!("Unknown dialect '{0}'", name))?;
+
+        return into_c_string(dialect.format(&signature.bytes));
+    });
+}
+
+//  Scan `view` for a signature, writing up to `capacity` addresses to `out`.
+//  With `resolve` set, each match is followed through the signature's resolve chain first.
+//  Returns the number of addresses written, or -1.
+#[no_mangle]
+pub unsafe extern "C" fn smtools_scan(view: *mut BNBinaryView, text: *const c_char, resolve: bool, out: *mut u64, capacity: usize) -> i64 {
+    return guard(-1, || {
+        let view = view_from_handle(view)?;
+        let signature = parse_full_signature(read_str(text, "Signature")?.to_owned())?;
+        if out.is_null() || capacity == 0 {
+            return Err("No room for matches".to_string());
+        }
+
+        let matches = find_signature(&signature.bytes, view.as_ref(), capacity);
+        for (index, address) in matches.iter().enumerate() {
+            let address = match resolve {
+                true => resolve_match(view.as_ref(), *address, &signature.resolve)?.last().copied().unwrap_or(*address),
+                false => *address,
+            };
+            *out.add(index) = address;
+        }
+
+        return Ok(matches.len() as i64);
+    });
+}
+
+//  Generate a signature at `address` with the view's generator settings.
+//  Returns generic bytes, the same text the parser accepts.
+#[no_mangle]
+pub unsafe extern "C" fn smtools_generate(view: *mut BNBinaryView, address: u64) -> *mut c_char {
+    return guard(std::ptr::null_mut(), || {
+        let view = view_from_handle(view)?;
+        if !view.offset_valid(address) {
+            return Err("Invalid address".to_string());
+        }
+
+        let func = find_address_base(view.as_ref(), address)?;
+        let buf = read_view(view.as_ref());
+        let bytes = linear_generate_signature_with(view.as_ref(), &buf, address, func, &generate_options(view.as_ref()))?;
+
+        return into_c_string(Signature::new(bytes).to_str_full());
+    });
+}
+
+//  Verify every signature of a gamedata file that applies to this view.
+//  Returns JSON: [{"name", "ok", "status"}]
+#[no_mangle]
+pub unsafe extern "C" fn smtools_verify_gamedata(view: *mut BNBinaryView, path: *const c_char) -> *mut c_char {
+    return guard(std::ptr::null_mut(), || {
+        let view = view_from_handle(view)?;
+        let path = read_str(path, "Path")?;
+
+        let buf = read_view(view.as_ref());
+        let results = verify_gamedata_file(view.as_ref(), &buf, path);
+        if let Some(VerifyStatus::Invalid(reason)) = results.iter().find(|r| r.name == "(file)").map(|r| r.status.to_owned()) {
+            return Err(reason);
+        }
+
+        let entries: Vec<serde_json::Value> = results.iter().map(|result| serde_json::json!({
+            "name": result.name,
+            "ok": result.status == VerifyStatus::Ok,
+            "status": result.status.describe(),
+        })).collect();
+
+        return into_c_string(serde_json::Value::Array(entries).to_string());
+    });
+}
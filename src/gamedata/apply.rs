@@ -0,0 +1,252 @@
+
+//  Apply:
+//  Name (and type) functions in a stripped binary from the signatures in a gamedata file.
+//  Only entries with exactly one match are applied, everything else is reported.
+
+use binaryninja::architecture::{Architecture, CoreArchitecture};
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::callingconvention::CallingConvention;
+use binaryninja::rc::Ref;
+use binaryninja::symbol::{Symbol, SymbolType};
+use binaryninja::types::{max_confidence, Conf, FunctionParameter, Type};
+use log::info;
+use crate::gamedata::file::{FunctionEntry, Gamedata};
+use crate::gamedata::writer::{library_name, platform_key, platform_os};
+use crate::signatures::scan::find_in_buffer;
+use crate::signatures::sigbyte::parse_signature;
+use crate::utils::function::find_address_base;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApplyStatus {
+    Applied,
+    Missing,
+    Ambiguous(usize),
+    Skipped(String),
+}
+
+pub struct ApplyResult {
+    pub name: String,
+    pub status: ApplyStatus,
+    pub address: Option<u64>,
+    //  Name the function had before, if it had a different one
+    pub previous: Option<String>,
+    pub created: bool,
+    pub typed: bool,
+    //  Why a type from "Functions" could not be applied
+    pub type_error: Option<String>,
+}
+
+impl ApplyStatus
+{
+    pub fn describe(&self) -> String {
+        match &self {
+            ApplyStatus::Applied => "applied".to_owned(),
+            ApplyStatus::Missing => "MISSING".to_owned(),
+            ApplyStatus::Ambiguous(count) => format!("AMBIGUOUS ({0}+ matches)", count),
+            ApplyStatus::Skipped(reason) => format!("skipped: {0}", reason),
+        }
+    }
+}
+
+//  Binary Ninja type for a DHooks argument/return type
+fn dhooks_type(arch: &CoreArchitecture, name: &str) -> Ref<Type> {
+    return match name.to_lowercase().as_str() {
+        "void" => Type::void(),
+        "bool" => Type::bool(),
+        "int" => Type::int(4, true),
+        "float" => Type::float(4),
+        "vector" => Type::array(&Type::float(4), 3),
+        "charptr" | "string" => Type::pointer(arch, &Type::int(1, true)),
+        //  cbaseentity, objectptr, vectorptr, edict and anything else we don't know
+        _ => Type::pointer(arch, &Type::void()),
+    };
+}
+
+fn find_calling_convention(view: &BinaryView, name: &str) -> Option<Ref<CallingConvention<CoreArchitecture>>> {
+    let platform = view.default_platform()?;
+
+    //  64-bit platforms have one convention each (sysv, win64) whatever the gamedata says
+    if view.address_size() == 8 {
+        return platform.get_default_calling_convention();
+    }
+
+    //  Outside Windows "thiscall" is cdecl with `this` as the first argument
+    let name = match (name, platform_os(view)) {
+        ("thiscall", "windows") => "thiscall",
+        ("thiscall", _) => "cdecl",
+        (name, _) => name,
+    };
+
+    return platform.calling_conventions().iter()
+        .find(|cc| cc.name().to_string().eq_ignore_ascii_case(name))
+        .map(|cc| cc.to_owned())
+        .or_else(|| platform.get_default_calling_convention());
+}
+
+fn function_type(view: &BinaryView, entry: &FunctionEntry) -> Result<Ref<Type>, String> {
+    let arch = view.default_arch().ok_or("View has no architecture".to_string())?;
+    let platform = platform_key(view);
+
+    let callconv = entry.callconv_for(&platform).unwrap_or("cdecl").to_lowercase();
+    let cc = find_calling_convention(view, &callconv)
+        .ok_or(format!("No '{0}' calling convention on this platform", callconv))?;
+
+    let mut parameters: Vec<FunctionParameter<String>> = Vec::new();
+    if callconv == "thiscall" {
+        parameters.push(FunctionParameter::new(Type::pointer(&arch, &Type::void()), "this".to_owned(), None));
+    }
+    for (name, kind) in entry.arguments.iter() {
+        parameters.push(FunctionParameter::new(dhooks_type(&arch, kind), name.to_owned(), None));
+    }
+
+    let return_type = dhooks_type(&arch, entry.return_type.as_deref().unwrap_or("void"));
+
+    return Ok(Type::function_with_options(
+        &return_type,
+        &parameters,
+        false,
+        &Conf::new(cc.as_ref(), max_confidence()),
+        Conf::new(0, 0),
+    ));
+}
+
+fn apply_entry(view: &BinaryView, buf: &Vec<u8>, name: &str, text: &str, function: Option<&FunctionEntry>) -> ApplyResult {
+    let mut result = ApplyResult { name: name.to_owned(), status: ApplyStatus::Applied, address: None, previous: None, created: false, typed: false, type_error: None };
+
+    if text.starts_with('@') {
+        result.status = ApplyStatus::Skipped("symbol lookup".to_owned());
+        return result;
+    }
+
+    let signature = match parse_signature(text.to_owned()) {
+        Ok(signature) => signature,
+        Err(msg) => {
+            result.status = ApplyStatus::Skipped(format!("failed to parse: {0}", msg));
+            return result;
+        }
+    };
+
+    let matches = find_in_buffer(&signature, buf, view.start(), 2);
+    let address = match matches.len() {
+        0 => {
+            result.status = ApplyStatus::Missing;
+            return result;
+        }
+        1 => matches[0],
+        count => {
+            result.status = ApplyStatus::Ambiguous(count);
+            return result;
+        }
+    };
+    result.address = Some(address);
+
+    let func = match find_address_base(view, address) {
+        Ok(func) if func.start() == address => func,
+        //  Signatures into the middle of a function are skipped rather than splitting it into a new one
+        Ok(_) => {
+            result.status = ApplyStatus::Skipped("match is inside another function".to_owned());
+            return result;
+        }
+        Err(_) => {
+            let platform = match view.default_platform() {
+                Some(platform) => platform,
+                None => {
+                    result.status = ApplyStatus::Skipped("view has no platform".to_owned());
+                    return result;
+                }
+            };
+            match view.add_auto_function(&platform, address) {
+                Some(func) => {
+                    result.created = true;
+                    func
+                }
+                None => {
+                    result.status = ApplyStatus::Skipped("failed to define a function".to_owned());
+                    return result;
+                }
+            }
+        }
+    };
+
+    let previous = func.symbol().full_name().to_string();
+    if previous != name {
+        result.previous = Some(previous);
+    }
+
+    let symbol = Symbol::builder(SymbolType::Function, name, address).create();
+    view.define_user_symbol(&symbol);
+
+    if let Some(function) = function {
+        match function_type(view, function) {
+            Ok(kind) => {
+                func.set_user_type(&kind);
+                result.typed = true;
+            }
+            Err(reason) => result.type_error = Some(reason),
+        }
+    }
+
+    return result;
+}
+
+pub fn apply_gamedata(view: &BinaryView, buf: &Vec<u8>, gamedata: &Gamedata) -> Vec<ApplyResult> {
+    let platform = platform_key(view);
+    let library = library_name(view);
+    let mut results = Vec::new();
+
+    for entry in gamedata.signatures.iter() {
+        if let Some(entry_library) = &entry.library {
+            if !entry_library.eq_ignore_ascii_case(&library) {
+                continue;
+            }
+        }
+
+        let text = match entry.for_platform(&platform) {
+            Some(text) => text,
+            None => {
+                info!("[SMTools] Skipping '{0}': no {1} signature", entry.name, platform);
+                continue;
+            }
+        };
+
+        //  DHooks entries name their signature, which is usually but not always their own name
+        let function = gamedata.functions.iter()
+            .find(|f| f.signature.as_deref().unwrap_or(&f.name).eq_ignore_ascii_case(&entry.name));
+
+        results.push(apply_entry(view, buf, &entry.name, text, function));
+    }
+
+    return results;
+}
+
+pub fn format_apply_table(results: &Vec<ApplyResult>) -> String {
+    let width = results.iter().map(|r| r.name.len()).max().unwrap_or(0).max(4);
+    let mut table = String::new();
+
+    table.push_str(&format!("{0:<width$}  {1:<10}  {2:<24}  {3}\n", "Name", "Address", "Status", "Notes", width = width));
+
+    for result in results.iter() {
+        let mut notes: Vec<String> = Vec::new();
+        if result.created {
+            notes.push("defined function".to_owned());
+        }
+        if let Some(previous) = &result.previous {
+            notes.push(format!("was {0}", previous));
+        }
+        if result.typed {
+            notes.push("typed".to_owned());
+        }
+        if let Some(reason) = &result.type_error {
+            notes.push(format!("type not applied: {0}", reason));
+        }
+
+        table.push_str(&format!("{0:<width$}  {1:<10}  {2:<24}  {3}\n",
+            result.name,
+            result.address.map(|a| format!("{0:#x}", a)).unwrap_or("-".to_owned()),
+            result.status.describe(),
+            notes.join(", "),
+            width = width));
+    }
+
+    return table;
+}
@@ -0,0 +1,122 @@
+use std::fs;
+use std::path::Path;
+use crate::gamedata::keyvalues::{parse_keyvalues, KeyValue};
+
+pub const PLATFORMS: [&str; 6] = ["windows", "linux", "mac", "windows64", "linux64", "mac64"];
+
+pub struct SignatureEntry {
+    pub name: String,
+    pub library: Option<String>,
+    //  Platform key to signature text, as written in the file
+    pub platforms: Vec<(String, String)>,
+}
+
+impl SignatureEntry
+{
+    pub fn for_platform(&self, platform: &str) -> Option<&str> {
+        return self.platforms.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(platform))
+            .map(|(_, value)| value.as_str());
+    }
+}
+
+//  A DHooks "Functions" entry
+pub struct FunctionEntry {
+    pub name: String,
+    pub signature: Option<String>,
+    pub callconv: Option<String>,
+    //  Platform key to calling convention, for entries that differ per platform
+    pub platform_callconv: Vec<(String, String)>,
+    pub return_type: Option<String>,
+    pub this: Option<String>,
+    //  Argument name to DHooks type, in order
+    pub arguments: Vec<(String, String)>,
+}
+
+impl FunctionEntry
+{
+    pub fn callconv_for(&self, platform: &str) -> Option<&str> {
+        return self.platform_callconv.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(platform))
+            .map(|(_, value)| value.as_str())
+            .or(self.callconv.as_deref());
+    }
+}
+
+pub struct Gamedata {
+    pub signatures: Vec<SignatureEntry>,
+    pub functions: Vec<FunctionEntry>,
+}
+
+fn parse_function(name: &str, entry: &KeyValue) -> FunctionEntry {
+    let arguments = match entry.get("arguments") {
+        Some(arguments) => arguments.children().iter()
+            .map(|(arg, value)| (arg.to_owned(), value.get_str("type").unwrap_or("int").to_owned()))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let platform_callconv = PLATFORMS.iter()
+        .filter_map(|platform| entry.get(platform)
+            .and_then(|section| section.get_str("callconv"))
+            .map(|callconv| (platform.to_string(), callconv.to_owned())))
+        .collect();
+
+    return FunctionEntry {
+        name: name.to_owned(),
+        signature: entry.get_str("signature").map(|s| s.to_owned()),
+        callconv: entry.get_str("callconv").map(|s| s.to_owned()),
+        platform_callconv,
+        return_type: entry.get_str("return").map(|s| s.to_owned()),
+        this: entry.get_str("this").map(|s| s.to_owned()),
+        arguments,
+    };
+}
+
+pub fn parse_gamedata(text: &str) -> Result<Gamedata, String> {
+    let root = parse_keyvalues(text)?;
+
+    let mut sections = Vec::new();
+    root.find_sections("Signatures", &mut sections);
+
+    let mut signatures: Vec<SignatureEntry> = Vec::new();
+
+    for section in sections.into_iter() {
+        for (name, entry) in section.children().iter() {
+            if let KeyValue::Value(_) = entry {
+                continue;
+            }
+
+            let platforms = PLATFORMS.iter()
+                .filter_map(|platform| entry.get_str(platform).map(|sig| (platform.to_string(), sig.to_owned())))
+                .collect();
+
+            signatures.push(SignatureEntry {
+                name: name.to_owned(),
+                library: entry.get_str("library").map(|s| s.to_owned()),
+                platforms,
+            });
+        }
+    }
+
+    let mut sections = Vec::new();
+    root.find_sections("Functions", &mut sections);
+
+    let mut functions: Vec<FunctionEntry> = Vec::new();
+    for section in sections.into_iter() {
+        for (name, entry) in section.children().iter() {
+            if let KeyValue::Section(_) = entry {
+                functions.push(parse_function(name, entry));
+            }
+        }
+    }
+
+    return Ok(Gamedata { signatures, functions });
+}
+
+pub fn load_gamedata(path: &Path) -> Result<Gamedata, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {0}: {1}", path.display(), err.to_string()))?;
+
+    return parse_gamedata(&text);
+}
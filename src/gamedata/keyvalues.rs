@@ -0,0 +1,229 @@
+
+//  KeyValues:
+//  Parser for the Valve KeyValues text format used by SourceMod gamedata files.
+//  Escapes are left as written, since gamedata signatures use "\x" literally.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyValue {
+    Value(String),
+    Section(Vec<(String, KeyValue)>),
+}
+
+impl KeyValue
+{
+    pub fn children(&self) -> &[(String, KeyValue)] {
+        match &self {
+            KeyValue::Section(children) => children.as_slice(),
+            KeyValue::Value(_) => &[],
+        }
+    }
+
+    //  First direct child with this key (case-insensitive, like SourceMod)
+    pub fn get(&self, key: &str) -> Option<&KeyValue> {
+        return self.children().iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value);
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        return match self.get(key) {
+            Some(KeyValue::Value(value)) => Some(value.as_str()),
+            _ => None,
+        };
+    }
+
+    //  Every section with this key, at any depth
+    pub fn find_sections<'a>(&'a self, key: &str, found: &mut Vec<&'a KeyValue>) {
+        for (name, value) in self.children().iter() {
+            if let KeyValue::Section(_) = value {
+                if name.eq_ignore_ascii_case(key) {
+                    found.push(value);
+                }
+                value.find_sections(key, found);
+            }
+        }
+    }
+}
+
+enum Token {
+    String(String),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let mut index = 0;
+    let mut line = 1;
+
+    while index < chars.len() {
+        let c = chars[index];
+
+        match c {
+            '\n' => {
+                line += 1;
+                index += 1;
+            }
+            c if c.is_whitespace() => index += 1,
+            '{' => {
+                tokens.push(Token::Open);
+                index += 1;
+            }
+            '}' => {
+                tokens.push(Token::Close);
+                index += 1;
+            }
+            '/' if chars.get(index + 1) == Some(&'/') => {
+                while index < chars.len() && chars[index] != '\n' {
+                    index += 1;
+                }
+            }
+            '/' if chars.get(index + 1) == Some(&'*') => {
+                index += 2;
+                while index < chars.len() && !(chars[index] == '*' && chars.get(index + 1) == Some(&'/')) {
+                    if chars[index] == '\n' {
+                        line += 1;
+                    }
+                    index += 1;
+                }
+                index += 2;
+            }
+            '"' => {
+                let mut value = String::new();
+                index += 1;
+
+                loop {
+                    match chars.get(index) {
+                        None => return Err(format!("Unterminated string on line {0}", line)),
+                        Some('"') => break,
+                        Some('\\') if chars.get(index + 1) == Some(&'"') => {
+                            value.push('"');
+                            index += 2;
+                        }
+                        Some(c) => {
+                            if *c == '\n' {
+                                line += 1;
+                            }
+                            value.push(*c);
+                            index += 1;
+                        }
+                    }
+                }
+
+                index += 1;
+                tokens.push(Token::String(value));
+            }
+            _ => {
+                let mut value = String::new();
+                while index < chars.len() && !chars[index].is_whitespace() && !['{', '}', '"'].contains(&chars[index]) {
+                    value.push(chars[index]);
+                    index += 1;
+                }
+                tokens.push(Token::String(value));
+            }
+        }
+    }
+
+    return Ok(tokens);
+}
+
+fn parse_section(tokens: &Vec<Token>, index: &mut usize, nested: bool) -> Result<Vec<(String, KeyValue)>, String> {
+    let mut children = Vec::new();
+
+    loop {
+        let key = match tokens.get(*index) {
+            None if nested => return Err("Unexpected end of file, missing '}'".to_string()),
+            None => return Ok(children),
+            Some(Token::Close) if nested => {
+                *index += 1;
+                return Ok(children);
+            }
+            Some(Token::Close) => return Err("Unexpected '}'".to_string()),
+            Some(Token::Open) => return Err("Unexpected '{', expected a key".to_string()),
+            Some(Token::String(key)) => key.to_owned(),
+        };
+        *index += 1;
+
+        match tokens.get(*index) {
+            Some(Token::String(value)) => {
+                children.push((key, KeyValue::Value(value.to_owned())));
+                *index += 1;
+            }
+            Some(Token::Open) => {
+                *index += 1;
+                let section = parse_section(tokens, index, true)?;
+                children.push((key, KeyValue::Section(section)));
+            }
+            _ => return Err(format!("Missing value for key '{0}'", key)),
+        }
+    }
+}
+
+pub fn parse_keyvalues(text: &str) -> Result<KeyValue, String> {
+    let tokens = tokenize(text)?;
+    let mut index = 0;
+
+    return Ok(KeyValue::Section(parse_section(&tokens, &mut index, false)?));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signatures::sigbyte::{parse_signature, SigByte};
+
+    #[test]
+    fn parses_nested_sections() {
+        let root = parse_keyvalues("\"Games\" { \"tf\" { \"Offsets\" { \"GetMaxHealth\" { \"windows\" \"123\" linux 124 } } } }").unwrap();
+
+        let mut offsets = Vec::new();
+        root.find_sections("offsets", &mut offsets);
+        assert_eq!(offsets.len(), 1);
+
+        let health = offsets[0].get("getmaxhealth").unwrap();
+        assert_eq!(health.get_str("windows"), Some("123"));
+        assert_eq!(health.get_str("Linux"), Some("124"));
+        assert_eq!(health.get_str("mac"), None);
+        assert_eq!(root.get("Games").unwrap().children().len(), 1);
+    }
+
+    #[test]
+    fn keeps_escapes_as_written() {
+        let root = parse_keyvalues(r#""name" "say \"hi\"" "path" "a\nb\\c""#).unwrap();
+
+        assert_eq!(root.get_str("name"), Some("say \"hi\""));
+        assert_eq!(root.get_str("path"), Some(r"a\nb\\c"));
+    }
+
+    #[test]
+    fn skips_comments() {
+        let text = "// header\n\"a\" \"1\" // trailing\n/* block\n \"b\" \"2\" */ \"c\" \"3\"";
+        let root = parse_keyvalues(text).unwrap();
+
+        assert_eq!(root.get_str("a"), Some("1"));
+        assert_eq!(root.get_str("b"), None);
+        assert_eq!(root.get_str("c"), Some("3"));
+    }
+
+    #[test]
+    fn reads_sourcemod_signatures() {
+        let root = parse_keyvalues(r#""Signatures" { "upper" { "windows" "\x55\x8B\x2A\x2A" } "lower" { "windows" "\x55\x8b\x2a\x2a" } }"#).unwrap();
+        let signatures = root.get("Signatures").unwrap();
+
+        let upper = signatures.get("upper").unwrap().get_str("windows").unwrap();
+        let lower = signatures.get("lower").unwrap().get_str("windows").unwrap();
+        assert_eq!(upper, r"\x55\x8B\x2A\x2A");
+
+        let expected = vec![SigByte::Match(0x55), SigByte::Match(0x8B), SigByte::Wildcard, SigByte::Wildcard];
+        assert_eq!(parse_signature(upper.to_owned()).unwrap(), expected);
+        assert_eq!(parse_signature(lower.to_owned()).unwrap(), expected);
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        assert!(parse_keyvalues("\"a\" { \"b\" \"1\"").is_err());
+        assert!(parse_keyvalues("\"a\" \"1\" }").is_err());
+        assert!(parse_keyvalues("\"a\" \"unterminated").is_err());
+        assert!(parse_keyvalues("\"a\"").is_err());
+    }
+}
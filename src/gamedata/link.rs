@@ -0,0 +1,229 @@
+
+//  Link:
+//  Gamedata files linked to a library (eg. "server"). Links live in the user directory rather
+//  than in a .bndb, so every linked signature is re-verified when analysis of any view of that
+//  library completes, and a game update is noticed as soon as the new binary is opened.
+
+use std::collections::BTreeMap;
+use std::ffi::c_void;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Instant;
+use binaryninja::binaryninjacore_sys::{BNBinaryView, BNBinaryViewEventType, BNMessageBoxButtonSet, BNMessageBoxIcon, BNRegisterBinaryViewEvent};
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::interaction::show_message_box;
+use log::{info, warn};
+use crate::gamedata::file::load_gamedata;
+use crate::gamedata::writer::{library_name, platform_key};
+use crate::monkey::binaryview::{create_bv_from_monkey, MonkeyBinaryView};
+use crate::report::Report;
+use crate::signatures::scan::find_in_buffer;
+use crate::signatures::sigbyte::parse_signature;
+use crate::signatures::store::VerifyStatus;
+use crate::utils::function::read_view;
+
+const LINKS_FILE: &str = "smtools_gamedata_links.json";
+
+pub struct LinkedResult {
+    pub file: String,
+    pub name: String,
+    pub status: VerifyStatus,
+}
+
+fn links_path() -> Result<PathBuf, String> {
+    return binaryninja::user_directory()
+        .map(|directory| directory.join(LINKS_FILE))
+        .map_err(|_| "No Binary Ninja user directory".to_string());
+}
+
+//  Library name to linked gamedata files
+fn load_links() -> BTreeMap<String, Vec<String>> {
+    let text = match links_path().and_then(|path| fs::read_to_string(path).map_err(|err| err.to_string())) {
+        Ok(text) => text,
+        Err(_) => return BTreeMap::new(),
+    };
+
+    return match serde_json::from_str(&text) {
+        Ok(links) => links,
+        Err(err) => {
+            warn!("[SMTools] Ignoring unreadable linked gamedata: {0}", err);
+            BTreeMap::new()
+        }
+    };
+}
+
+pub fn linked_files(view: &BinaryView) -> Vec<String> {
+    return load_links().remove(&library_name(view)).unwrap_or_default();
+}
+
+fn save_linked_files(view: &BinaryView, files: &Vec<String>) {
+    let mut links = load_links();
+    if files.len() == 0 {
+        links.remove(&library_name(view));
+    }
+    else {
+        links.insert(library_name(view), files.to_owned());
+    }
+
+    let result = links_path().and_then(|path| {
+        let text = serde_json::to_string_pretty(&links).map_err(|err| err.to_string())?;
+        return fs::write(path, text).map_err(|err| err.to_string());
+    });
+    if let Err(err) = result {
+        warn!("[SMTools] Failed to store linked gamedata: {0}", err);
+    }
+}
+
+//  Only files that parse are linked, so a typo doesn't turn into a warning on every open
+pub fn link_gamedata(view: &BinaryView, path: &Path) -> Result<usize, String> {
+    let gamedata = load_gamedata(path)?;
+    let path = path.to_string_lossy().to_string();
+
+    let mut files = linked_files(view);
+    if !files.contains(&path) {
+        files.push(path);
+        save_linked_files(view, &files);
+    }
+
+    return Ok(gamedata.signatures.len());
+}
+
+pub fn unlink_gamedata(view: &BinaryView, path: &str) {
+    let mut files = linked_files(view);
+    files.retain(|file| file != path);
+    save_linked_files(view, &files);
+}
+
+fn verify_entry(view: &BinaryView, buf: &Vec<u8>, text: &str) -> VerifyStatus {
+    let signature = match parse_signature(text.to_owned()) {
+        Ok(signature) => signature,
+        Err(reason) => return VerifyStatus::Invalid(reason),
+    };
+
+    let matches = find_in_buffer(&signature, buf, view.start(), 2);
+    return match matches.len() {
+        0 => VerifyStatus::Missing,
+        1 => VerifyStatus::Ok,
+        count => VerifyStatus::Ambiguous(count),
+    };
+}
+
+//  Verify the signatures of one gamedata file that apply to this view's platform and library
+pub fn verify_gamedata_file(view: &BinaryView, buf: &Vec<u8>, file: &str) -> Vec<LinkedResult> {
+    let platform = platform_key(view);
+    let library = library_name(view);
+    let mut results = Vec::new();
+
+    let gamedata = match load_gamedata(Path::new(file)) {
+        Ok(gamedata) => gamedata,
+        Err(reason) => {
+            results.push(LinkedResult { file: file.to_owned(), name: "(file)".to_owned(), status: VerifyStatus::Invalid(reason) });
+            return results;
+        }
+    };
+
+    for entry in gamedata.signatures.iter() {
+        if let Some(entry_library) = &entry.library {
+            if !entry_library.eq_ignore_ascii_case(&library) {
+                continue;
+            }
+        }
+
+        //  Symbol lookups ("@name") have nothing to scan for
+        let text = match entry.for_platform(&platform) {
+            Some(text) if !text.starts_with('@') => text,
+            _ => continue,
+        };
+
+        results.push(LinkedResult {
+            file: file.to_owned(),
+            name: entry.name.to_owned(),
+            status: verify_entry(view, buf, text),
+        });
+    }
+
+    return results;
+}
+
+pub fn verify_linked(view: &BinaryView, buf: &Vec<u8>) -> Vec<LinkedResult> {
+    let mut results = Vec::new();
+
+    for file in linked_files(view).iter() {
+        results.extend(verify_gamedata_file(view, buf, file));
+    }
+
+    return results;
+}
+
+//  One line per broken entry, grouped by file
+pub fn format_linked_summary(results: &Vec<LinkedResult>) -> String {
+    let mut summary = String::new();
+    let mut current = "";
+
+    for result in results.iter().filter(|r| r.status != VerifyStatus::Ok) {
+        if result.file != current {
+            current = &result.file;
+            let name = Path::new(current).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or(current.to_owned());
+            summary.push_str(&format!("{0}:\n", name));
+        }
+        summary.push_str(&format!("    {0}: {1}\n", result.name, result.status.describe()));
+    }
+
+    return summary;
+}
+
+//  Verify every linked file, and warn if anything broke. Returns the number of broken entries.
+pub fn check_linked_gamedata(view: &BinaryView) -> usize {
+    let now = Instant::now();
+    let buf = read_view(view);
+    let results = verify_linked(view, &buf);
+    let broken = results.iter().filter(|r| r.status != VerifyStatus::Ok).count();
+
+    info!("[SMTools] Verified {0} linked gamedata entries in {1}ms, {2} broken", results.len(), now.elapsed().as_millis(), broken);
+
+    let table = results.iter().map(|result| vec![
+        result.file.to_owned(),
+        result.name.to_owned(),
+        result.status.describe(),
+    ]).collect();
+    Report::new("SMTools: Linked gamedata")
+        .note(&format!("{0} of {1} entries broken", broken, results.len()))
+        .table("Entries", &["File", "Entry", "Status"], table)
+        .timing("Verify", now.elapsed())
+        .publish(view);
+
+    if broken > 0 {
+        let summary = format_linked_summary(&results);
+        warn!("[SMTools] {0} linked gamedata entries are missing or ambiguous:\n{1}", broken, summary);
+        show_message_box("SMTools: linked gamedata", &format!("{0} of {1} signatures no longer match exactly once:\n\n{2}", broken, results.len(), summary), BNMessageBoxButtonSet::OKButtonSet, BNMessageBoxIcon::WarningIcon);
+    }
+
+    return broken;
+}
+
+unsafe extern "C" fn on_analysis_complete(_ctxt: *mut c_void, handle: *mut BNBinaryView) {
+    let monkey = MonkeyBinaryView { handle };
+    let view = create_bv_from_monkey(&monkey);
+
+    //  Checked here so views without linked files never have their bytes read
+    if linked_files(view).len() == 0 {
+        return;
+    }
+
+    //  Scanning can take a while, don't hold up the analysis thread
+    let view = view.to_owned();
+    thread::spawn(move || {
+        check_linked_gamedata(view.as_ref());
+    });
+}
+
+pub fn register_linked_verification() {
+    unsafe {
+        BNRegisterBinaryViewEvent(
+            BNBinaryViewEventType::BinaryViewInitialAnalysisCompletionEvent,
+            Some(on_analysis_complete),
+            std::ptr::null_mut(),
+        );
+    }
+}
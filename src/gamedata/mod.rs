@@ -0,0 +1,5 @@
+pub mod apply;
+pub mod file;
+pub mod keyvalues;
+pub mod link;
+pub mod writer;
\ No newline at end of file
@@ -0,0 +1,163 @@
+use std::path::Path;
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use crate::signatures::signature::{ResolveStep, Signature};
+
+//  Minimal KeyValues emitter, enough to print gamedata snippets
+//  that can be pasted straight into a SourceMod gamedata file.
+pub struct KeyValuesWriter {
+    out: String,
+    depth: usize,
+}
+
+impl KeyValuesWriter
+{
+    pub fn new() -> KeyValuesWriter {
+        return KeyValuesWriter { out: String::new(), depth: 0 };
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push('\t');
+        }
+    }
+
+    pub fn open(&mut self, key: &str) -> &mut KeyValuesWriter {
+        self.indent();
+        self.out.push_str(&format!("\"{0}\"\n", key));
+        self.indent();
+        self.out.push_str("{\n");
+        self.depth += 1;
+        return self;
+    }
+
+    pub fn close(&mut self) -> &mut KeyValuesWriter {
+        self.depth = self.depth.saturating_sub(1);
+        self.indent();
+        self.out.push_str("}\n");
+        return self;
+    }
+
+    pub fn value(&mut self, key: &str, value: &str) -> &mut KeyValuesWriter {
+        self.indent();
+        self.out.push_str(&format!("\"{0}\"\t\t\"{1}\"\n", key, value));
+        return self;
+    }
+
+    pub fn comment(&mut self, text: &str) -> &mut KeyValuesWriter {
+        self.indent();
+        self.out.push_str(&format!("// {0}\n", text));
+        return self;
+    }
+
+    pub fn finish(&self) -> String {
+        return self.out.to_owned();
+    }
+}
+
+//  Operating system part of the platform key: "windows", "linux" or "mac"
+pub fn platform_os(view: &BinaryView) -> &'static str {
+    let name = match view.default_platform() {
+        Some(platform) => platform.name().to_string(),
+        None => String::new(),
+    };
+
+    if name.contains("windows") {
+        return "windows";
+    }
+    if name.contains("mac") {
+        return "mac";
+    }
+    return "linux";
+}
+
+//  Gamedata platform key for the view ("windows", "linux" or "mac"),
+//  with a "64" suffix for 64-bit binaries
+pub fn platform_key(view: &BinaryView) -> String {
+    let os = platform_os(view);
+
+    return match view.address_size() {
+        8 => format!("{0}64", os),
+        _ => os.to_owned(),
+    };
+}
+
+//  Gamedata library name, eg. "server" for server.dll or server_srv.so
+pub fn library_name(view: &BinaryView) -> String {
+    let filename = view.file().filename().to_string();
+    let stem = Path::new(&filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or(filename.to_owned());
+
+    //  Strip ".so" from "server_srv.so.bndb" and friends
+    let stem = stem.split('.').next().unwrap_or("").to_owned();
+
+    return stem.trim_end_matches("_srv").to_owned();
+}
+
+//  Translate a resolve chain into the keys understood by SourceMod's "Addresses" section
+pub fn resolve_keys(steps: &Vec<ResolveStep>) -> Result<Vec<(String, String)>, String> {
+    let mut keys = Vec::new();
+    let mut pending: i64 = 0;
+
+    for step in steps.iter() {
+        match step {
+            ResolveStep::Offset(delta) => {
+                pending = pending.checked_add(*delta).ok_or("Resolve offsets overflow".to_string())?;
+            }
+            ResolveStep::Rel32 => {
+                keys.push(("read_offs32".to_owned(), pending.to_string()));
+                pending = 0;
+            }
+            //  "read" adds its value to the cursor and then dereferences it
+            ResolveStep::Deref(count) => {
+                for _ in 0..*count {
+                    keys.push(("read".to_owned(), pending.to_string()));
+                    pending = 0;
+                }
+            }
+        }
+    }
+
+    if pending != 0 {
+        keys.push(("offset".to_owned(), pending.to_string()));
+    }
+
+    return Ok(keys);
+}
+
+pub fn write_signature_entry(writer: &mut KeyValuesWriter, view: &BinaryView, name: &str, signature: &Signature) {
+    writer.open(name)
+        .value("library", &library_name(view))
+        .value(&platform_key(view), &signature.to_str_sm())
+        .close();
+}
+
+pub fn write_address_entry(writer: &mut KeyValuesWriter, view: &BinaryView, name: &str, signature_name: &str, signature: &Signature) -> Result<(), String> {
+    let keys = resolve_keys(&signature.resolve)?;
+
+    writer.open(name)
+        .value("signature", signature_name)
+        .open(&platform_key(view));
+    for (key, value) in keys.iter() {
+        writer.value(key, value);
+    }
+    writer.close().close();
+
+    return Ok(());
+}
+
+//  Print a "Signatures" + "Addresses" pair for a resolvable signature
+pub fn format_address_gamedata(view: &BinaryView, name: &str, signature: &Signature) -> Result<String, String> {
+    let mut writer = KeyValuesWriter::new();
+
+    writer.open("Signatures");
+    write_signature_entry(&mut writer, view, name, signature);
+    writer.close();
+
+    writer.open("Addresses");
+    write_address_entry(&mut writer, view, name, name, signature)?;
+    writer.close();
+
+    return Ok(writer.finish());
+}
@@ -1,4 +1,4 @@
-use std::{cell::Ref, ffi::c_void, fmt::Binary, thread, u8};
+use std::{cell::Ref, ffi::c_void, fmt::Binary, fs, ops::Range, thread, time::Instant, u8};
 
 use binaryninja::{
     architecture::Architecture,
@@ -7,7 +7,7 @@ use binaryninja::{
         BNGetConstantsReferencedByInstructionIfAvailable, BNWorkerEnqueue,
     },
     binaryview::{BinaryView, BinaryViewBase, BinaryViewExt},
-    command::{register_for_address, AddressCommand},
+    command::{register_for_address, AddressCommand, RangeCommand},
     disassembly::{DisassemblyTextLine, InstructionTextToken, InstructionTextTokenContents},
     flowgraph::{BranchType, EdgePenStyle, EdgeStyle, FlowGraph, FlowGraphNode, ThemeColor},
     function::Function,
@@ -17,7 +17,7 @@ use binaryninja::{
     string::BnString,
 };
 use binaryninja::command::{Command, FunctionCommand, register, register_for_function, register_for_range};
-use binaryninja::interaction::get_text_line_input;
+use binaryninja::interaction::{get_integer_input, get_open_filename_input, get_save_filename_input, get_text_line_input};
 use log::{debug, error, info, warn, LevelFilter};
 use monkey::{
     arch::create_monkey_arch,
@@ -25,12 +25,40 @@ use monkey::{
     function::create_monkey_function,
 };
 use signatures::sigbyte::SigByte;
-use crate::signatures::generate::generate_and_print_signature;
-use crate::signatures::scan::find_signature;
+use crate::signatures::batch::{batch_generate, format_batch_gamedata, format_batch_json, format_batch_table, parse_address_list, select_targets, BatchSelection};
+use crate::signatures::generate::generate_and_print_signature_with;
+use crate::signatures::generate_form::ask_generate_choices;
+use crate::signatures::explain::{clear_explanation, generate_and_explain_signature};
+use crate::signatures::scan::{clear_match_tags, find_signature, resolve_match, tag_matches, MATCH_TAG};
+use crate::signatures::candidates::generate_and_print_candidates;
+use crate::signatures::collision::{collision_report, print_collision_report};
+use crate::signatures::cross_version::generate_and_print_cross_version_signature;
+use crate::signatures::data_generate::generate_and_print_data_signature;
+use crate::gamedata::apply::{apply_gamedata, format_apply_table, ApplyStatus};
+use crate::gamedata::file::load_gamedata;
+use crate::gamedata::link::{check_linked_gamedata, link_gamedata, linked_files, register_linked_verification, unlink_gamedata};
+use crate::gamedata::writer::library_name;
+use crate::signatures::fuzzy::{fuzzy_scan, FuzzyOptions};
+use crate::signatures::index::{index_path, load_or_build_index};
+use crate::signatures::repair::{format_repair_report, repair_gamedata, repair_signature, RepairStatus};
+use crate::signatures::range_generate::generate_and_print_range_signature;
+use crate::signatures::member_generate::generate_and_print_member_signature;
+use crate::signatures::xref_generate::generate_and_print_xref_signature;
 use crate::signatures::sigbyte::parse_signature;
-use crate::utils::function::find_address_base;
+use crate::signatures::signature::parse_full_signature;
+use crate::signatures::store::{format_stored_gamedata, format_stored_json, load_signatures, remember_signature, save_signatures, verify_stored, VerifyStatus};
+use crate::signatures::survey::{format_survey_csv, format_survey_json, format_survey_table, sort_survey, survey_view, tag_unsignable, SurveySort};
+use crate::report::{address_link, last_report_json, Report};
+use crate::settings::{default_dialect, generate_options, max_matches, register_settings, should_tag_matches};
+use crate::signatures::dialect::log_signature;
+use crate::utils::function::{find_address_base, read_view};
+use crate::utils::interaction::get_choice_input;
 
+pub mod api;
+pub mod gamedata;
 pub mod monkey;
+pub mod report;
+pub mod settings;
 pub mod signatures;
 pub mod utils;
 
@@ -41,7 +69,9 @@ struct GenerateSignatureCommand;
 
 impl AddressCommand for GenerateSignatureCommand {
     fn action(&self, view: &BinaryView, addr: u64) {
-        generate_and_print_signature(view, addr);
+        if let Some(choices) = ask_generate_choices(view) {
+            generate_and_print_signature_with(view, addr, &choices);
+        }
     }
 
     fn valid(&self, view: &BinaryView, addr: u64) -> bool {
@@ -54,7 +84,9 @@ struct GenerateFuncSignatureCommand;
 
 impl FunctionCommand for GenerateFuncSignatureCommand {
     fn action(&self, view: &BinaryView, func: &Function) {
-        generate_and_print_signature(view, func.start());
+        if let Some(choices) = ask_generate_choices(view) {
+            generate_and_print_signature_with(view, func.start(), &choices);
+        }
     }
 
     fn valid(&self, view: &BinaryView, func: &Function) -> bool {
@@ -63,6 +95,102 @@ impl FunctionCommand for GenerateFuncSignatureCommand {
     }
 }
 
+struct ExplainSignatureCommand;
+
+impl AddressCommand for ExplainSignatureCommand {
+    fn action(&self, view: &BinaryView, addr: u64) {
+        generate_and_explain_signature(view, addr);
+    }
+
+    fn valid(&self, view: &BinaryView, addr: u64) -> bool {
+        find_address_base(view, addr).is_ok()
+    }
+}
+
+struct ClearExplanationCommand;
+
+impl Command for ClearExplanationCommand {
+    fn action(&self, view: &BinaryView) {
+        let cleared = clear_explanation(view);
+        info!("[SMTools] Cleared highlights and comments from {0} instructions", cleared);
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct GenerateCandidatesCommand;
+
+impl AddressCommand for GenerateCandidatesCommand {
+    fn action(&self, view: &BinaryView, addr: u64) {
+        generate_and_print_candidates(view, addr);
+    }
+
+    fn valid(&self, view: &BinaryView, addr: u64) -> bool {
+        find_address_base(view, addr).is_ok()
+    }
+}
+
+struct GenerateXrefSignatureCommand;
+
+impl FunctionCommand for GenerateXrefSignatureCommand {
+    fn action(&self, view: &BinaryView, func: &Function) {
+        generate_and_print_xref_signature(view, func.start());
+    }
+
+    fn valid(&self, view: &BinaryView, func: &Function) -> bool {
+        true
+    }
+}
+
+struct GenerateCrossVersionSignatureCommand;
+
+impl FunctionCommand for GenerateCrossVersionSignatureCommand {
+    fn action(&self, view: &BinaryView, func: &Function) {
+        generate_and_print_cross_version_signature(view, func);
+    }
+
+    fn valid(&self, view: &BinaryView, func: &Function) -> bool {
+        true
+    }
+}
+
+struct GenerateDataSignatureCommand;
+
+impl AddressCommand for GenerateDataSignatureCommand {
+    fn action(&self, view: &BinaryView, addr: u64) {
+        generate_and_print_data_signature(view, addr);
+    }
+
+    fn valid(&self, view: &BinaryView, addr: u64) -> bool {
+        view.offset_valid(addr)
+    }
+}
+
+struct GenerateMemberSignatureCommand;
+
+impl AddressCommand for GenerateMemberSignatureCommand {
+    fn action(&self, view: &BinaryView, addr: u64) {
+        generate_and_print_member_signature(view, addr);
+    }
+
+    fn valid(&self, view: &BinaryView, addr: u64) -> bool {
+        find_address_base(view, addr).is_ok()
+    }
+}
+
+struct GenerateRangeSignatureCommand;
+
+impl RangeCommand for GenerateRangeSignatureCommand {
+    fn action(&self, view: &BinaryView, range: Range<u64>) {
+        generate_and_print_range_signature(view, range);
+    }
+
+    fn valid(&self, view: &BinaryView, range: Range<u64>) -> bool {
+        range.end > range.start
+    }
+}
+
 struct FindSignatureCommand;
 
 impl Command for FindSignatureCommand {
@@ -71,27 +199,81 @@ impl Command for FindSignatureCommand {
         match signature
         {
             Some(sig_str)   => {
-                let parsed = parse_signature(sig_str);
+                let parsed = parse_full_signature(sig_str);
                 match parsed
                 {
                     Ok(sig) => {
-                        info!("[SMTools] Parsed signature! {0:?}", sig);
-                        let matches = find_signature(&sig, view, 50);
-                        info!("[SMTools] First 50 matches:");
+                        info!("[SMTools] Parsed signature! {0:?}", sig.bytes);
+                        for (index, step) in sig.resolve.iter().enumerate() {
+                            info!("[SMTools] Resolve {0}: {1}", index + 1, step.describe());
+                        }
+
+                        let limit = max_matches(view);
+                        let now = Instant::now();
+                        let matches = find_signature(&sig.bytes, view, limit);
+                        let mut report = Report::new("SMTools: Find Signature");
+                        report.signature("signature", matches.first().copied().unwrap_or(0), &sig)
+                            .note(&format!("{0} match(es), up to {1}", matches.len(), limit))
+                            .timing("Scan", now.elapsed());
+                        info!("[SMTools] First {0} matches:", limit);
                         if matches.len() == 0
                         {
                             log::warn!("[SMTools] No matches!");
                         }
 
+                        if matches.len() > 1
+                        {
+                            let collisions = collision_report(view, &sig.bytes, matches[0], 10);
+                            info!("[SMTools] Comparing other matches against {0:#08X}", matches[0]);
+                            print_collision_report(matches[0], sig.bytes.len(), &collisions);
+                        }
+
+                        //  Tag every match, not just the ones listed
+                        if should_tag_matches(view) && matches.len() > 0
+                        {
+                            let all_matches = find_signature(&sig.bytes, view, usize::MAX);
+                            let tagged = tag_matches(view, &sig.to_str_full(), &all_matches);
+                            info!("[SMTools] Tagged {0} match(es) as '{1}'", tagged, MATCH_TAG);
+                        }
+
                         for sig_match in matches.into_iter() {
                             let func_scan = find_address_base(view, sig_match);
                             match func_scan
                             {
-                                Ok(func) => info!("[SMTools] Match at {0:#08X} ({1} @ {2:#08X})", sig_match, func.symbol().full_name(), func.start()),
-                                Err(msg) => info!("[SMTools] Match at {0:#08X} (no func: {1})", sig_match, msg)
+                                Ok(func) => {
+                                    info!("[SMTools] Match at {0:#08X} ({1} @ {2:#08X})", sig_match, func.symbol().full_name(), func.start());
+                                    report.add_match(sig_match, &format!("{0} + {1:#x}", func.symbol().full_name(), sig_match - func.start()), "");
+                                }
+                                Err(msg) => {
+                                    info!("[SMTools] Match at {0:#08X} (no func: {1})", sig_match, msg);
+                                    report.add_match(sig_match, "no func", "");
+                                }
+                            }
+
+                            if sig.resolve.len() == 0 {
+                                continue;
+                            }
+
+                            match resolve_match(view, sig_match, &sig.resolve)
+                            {
+                                Ok(hops) => {
+                                    if let Some(target) = hops.last() {
+                                        report.note(&format!("{0} resolves to {1}", address_link(sig_match), address_link(*target)));
+                                    }
+                                    for (step, hop) in sig.resolve.iter().zip(hops.iter()) {
+                                        match find_address_base(view, *hop)
+                                        {
+                                            Ok(func) => info!("[SMTools]     {0} -> {1:#08X} ({2} @ {3:#08X})", step.describe(), hop, func.symbol().full_name(), func.start()),
+                                            Err(_) => info!("[SMTools]     {0} -> {1:#08X}", step.describe(), hop)
+                                        }
+                                    }
+                                }
+                                Err(msg) => warn!("[SMTools]     Failed to resolve: {0}", msg)
                             }
 
                         }
+
+                        report.publish(view);
                     }
                     Err(msg) => {
                         warn!("[SMTools] Failed to parse signature: {0}", msg);
@@ -108,15 +290,620 @@ impl Command for FindSignatureCommand {
     }
 }
 
+struct BatchGenerateCommand;
+
+impl Command for BatchGenerateCommand {
+    fn action(&self, view: &BinaryView) {
+        let selection = match get_choice_input("Select functions by", "SMTools", &["Symbol pattern", "Tag", "Address list"]) {
+            Some(0) => get_text_line_input("Symbol pattern (eg. CTFPlayer::*)", "SMTools").map(BatchSelection::Pattern),
+            Some(1) => get_text_line_input("Tag type", "SMTools").map(BatchSelection::Tag),
+            Some(_) => match get_text_line_input("Addresses", "SMTools").map(|text| parse_address_list(&text)) {
+                Some(Ok(addresses)) => Some(BatchSelection::Addresses(addresses)),
+                Some(Err(msg)) => {
+                    warn!("[SMTools] {0}", msg);
+                    return;
+                }
+                None => None,
+            },
+            None => None,
+        };
+
+        let selection = match selection {
+            Some(selection) => selection,
+            None => return,
+        };
+
+        let targets = select_targets(view, &selection);
+        if targets.len() == 0 {
+            warn!("[SMTools] No functions selected");
+            return;
+        }
+
+        let export = match get_choice_input("Export", "SMTools", &["Gamedata", "JSON", "Log only"]) {
+            Some(0) => get_save_filename_input("Gamedata file", "*.txt", "signatures.txt").map(|path| (path, false)),
+            Some(1) => get_save_filename_input("JSON file", "*.json", "signatures.json").map(|path| (path, true)),
+            _ => None,
+        };
+
+        let view = view.to_owned();
+        thread::spawn(move || {
+            let task = match binaryninja::backgroundtask::BackgroundTask::new("SMTools: batch generation", true) {
+                Ok(task) => task,
+                Err(_) => {
+                    warn!("[SMTools] Failed to start background task");
+                    return;
+                }
+            };
+
+            let now = Instant::now();
+            let options = generate_options(view.as_ref());
+            let rows = batch_generate(view.as_ref(), &targets, &options, Some(task.as_ref()));
+            task.finish();
+
+            for row in rows.iter() {
+                if let Some(signature) = &row.signature {
+                    remember_signature(view.as_ref(), &row.name, row.address, signature, default_dialect(view.as_ref()), options.policy);
+                }
+            }
+
+            let succeeded = rows.iter().filter(|r| r.succeeded()).count();
+            info!("[SMTools] Batch completed in {0}ms: {1} of {2} functions signed", now.elapsed().as_millis(), succeeded, rows.len());
+            info!("[SMTools] Batch results:\n{0}", format_batch_table(&rows));
+
+            let mut report = Report::new("SMTools: Batch generation");
+            report.note(&format!("{0} of {1} functions signed", succeeded, rows.len()))
+                .timing("Batch", now.elapsed());
+            for row in rows.iter() {
+                match &row.signature {
+                    Some(signature) => { report.signature(&row.name, row.address, signature); }
+                    None => { report.add_match(row.address, &row.name, &format!("FAILED: {0}", row.reason.to_owned().unwrap_or_default())); }
+                }
+            }
+            report.publish(view.as_ref());
+
+            if let Some((path, json)) = export {
+                let output = if json { format_batch_json(&rows) } else { format_batch_gamedata(view.as_ref(), &rows) };
+                match output {
+                    Ok(text) => {
+                        if let Err(err) = fs::write(&path, text) {
+                            warn!("[SMTools] Failed to write {0}: {1}", path.display(), err);
+                        }
+                    }
+                    Err(msg) => warn!("[SMTools] Failed to format export: {0}", msg),
+                }
+            }
+        });
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct SurveyCommand;
+
+impl Command for SurveyCommand {
+    fn action(&self, view: &BinaryView) {
+        let names: Vec<&str> = SurveySort::ALL.iter().map(|s| s.name()).collect();
+        let sort = match get_choice_input("Sort by", "SMTools", &names) {
+            Some(choice) => SurveySort::ALL[choice],
+            None => return,
+        };
+
+        let export = match get_choice_input("Export", "SMTools", &["CSV", "JSON", "Log only"]) {
+            Some(0) => get_save_filename_input("CSV file", "*.csv", "survey.csv").map(|path| (path, false)),
+            Some(1) => get_save_filename_input("JSON file", "*.json", "survey.json").map(|path| (path, true)),
+            _ => None,
+        };
+
+        let view = view.to_owned();
+        thread::spawn(move || {
+            let task = match binaryninja::backgroundtask::BackgroundTask::new("SMTools: signability survey", true) {
+                Ok(task) => task,
+                Err(_) => {
+                    warn!("[SMTools] Failed to start background task");
+                    return;
+                }
+            };
+
+            let buf = read_view(view.as_ref());
+            let now = Instant::now();
+            let mut rows = survey_view(view.as_ref(), &buf, Some(task.as_ref()));
+            task.finish();
+
+            sort_survey(&mut rows, sort);
+            let tagged = tag_unsignable(view.as_ref(), &rows);
+            let xrefs = rows.iter().filter(|r| r.xref).count();
+
+            info!("[SMTools] Survey completed in {0}ms: {1} functions, {2} need an xref, {3} unsignable (tagged '{4}')",
+                now.elapsed().as_millis(), rows.len(), xrefs, tagged, crate::signatures::survey::UNSIGNABLE_TAG);
+            info!("[SMTools] Survey by {0}:\n{1}", sort.name(), format_survey_table(&rows));
+
+            let table = rows.iter().map(|row| vec![
+                row.name.to_owned(),
+                address_link(row.address),
+                row.length.map(|l| l.to_string()).unwrap_or("-".to_owned()),
+                format!("{0:.1}%", row.wildcard_ratio() * 100.0),
+                if row.xref { "yes".to_owned() } else { "no".to_owned() },
+                row.reason.to_owned().unwrap_or_default(),
+            ]).collect();
+            Report::new("SMTools: Signability survey")
+                .note(&format!("{0} functions, {1} need an xref, {2} unsignable", rows.len(), xrefs, tagged))
+                .table(&format!("Functions by {0}", sort.name()), &["Name", "Address", "Length", "Wildcards", "Xref", "Reason"], table)
+                .timing("Survey", now.elapsed())
+                .publish(view.as_ref());
+
+            if let Some((path, json)) = export {
+                let output = if json { format_survey_json(&rows) } else { Ok(format_survey_csv(&rows)) };
+                match output {
+                    Ok(text) => {
+                        if let Err(err) = fs::write(&path, text) {
+                            warn!("[SMTools] Failed to write {0}: {1}", path.display(), err);
+                        }
+                    }
+                    Err(msg) => warn!("[SMTools] Failed to format export: {0}", msg),
+                }
+            }
+        });
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct ManageSignaturesCommand;
+
+impl Command for ManageSignaturesCommand {
+    fn action(&self, view: &BinaryView) {
+        let mut stored = load_signatures(view);
+        if stored.len() == 0 {
+            warn!("[SMTools] No signatures stored in this view");
+            return;
+        }
+
+        info!("[SMTools] {0} stored signature(s):", stored.len());
+        for entry in stored.iter() {
+            info!("[SMTools] {0} @ {1:#08X}: {2} ({3} policy, {4})", entry.name, entry.target, entry.signature, entry.policy, entry.format);
+        }
+
+        let names: Vec<String> = stored.iter().map(|entry| format!("{0} @ {1:#08X}", entry.name, entry.target)).collect();
+        let names: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
+
+        match get_choice_input("Action", "SMTools", &["Re-verify all", "Rename", "Delete", "Export gamedata", "Export JSON"]) {
+            Some(0) => {
+                let mut broken = 0;
+                for entry in stored.iter() {
+                    let status = verify_stored(view, entry);
+                    if status != VerifyStatus::Ok {
+                        broken += 1;
+                        warn!("[SMTools] {0}: {1}", entry.name, status.describe());
+                    }
+                    else {
+                        info!("[SMTools] {0}: {1}", entry.name, status.describe());
+                    }
+                }
+                info!("[SMTools] {0} of {1} stored signatures verified", stored.len() - broken, stored.len());
+            }
+            Some(1) => {
+                let index = match get_choice_input("Signature", "SMTools", &names) {
+                    Some(index) => index,
+                    None => return,
+                };
+                if let Some(name) = get_text_line_input("New name", "SMTools") {
+                    stored[index].name = name.trim().to_owned();
+                    save_signatures(view, &stored);
+                }
+            }
+            Some(2) => {
+                if let Some(index) = get_choice_input("Signature", "SMTools", &names) {
+                    let removed = stored.remove(index);
+                    save_signatures(view, &stored);
+                    info!("[SMTools] Deleted {0}", removed.name);
+                }
+            }
+            Some(3) => {
+                if let Some(path) = get_save_filename_input("Gamedata file", "*.txt", "signatures.txt") {
+                    match format_stored_gamedata(view, &stored).and_then(|text| fs::write(&path, text).map_err(|err| err.to_string())) {
+                        Ok(()) => info!("[SMTools] Exported {0} signatures to {1}", stored.len(), path.display()),
+                        Err(msg) => warn!("[SMTools] Failed to export: {0}", msg),
+                    }
+                }
+            }
+            Some(4) => {
+                if let Some(path) = get_save_filename_input("JSON file", "*.json", "signatures.json") {
+                    match format_stored_json(&stored).and_then(|text| fs::write(&path, text).map_err(|err| err.to_string())) {
+                        Ok(()) => info!("[SMTools] Exported {0} signatures to {1}", stored.len(), path.display()),
+                        Err(msg) => warn!("[SMTools] Failed to export: {0}", msg),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct ClearMatchTagsCommand;
+
+impl Command for ClearMatchTagsCommand {
+    fn action(&self, view: &BinaryView) {
+        let removed = clear_match_tags(view);
+        info!("[SMTools] Removed {0} '{1}' tag(s)", removed, MATCH_TAG);
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct FindFuzzySignatureCommand;
+
+impl Command for FindFuzzySignatureCommand {
+    fn action(&self, view: &BinaryView) {
+        let sig = match get_text_line_input("Signature", "SMTools").map(parse_full_signature) {
+            Some(Ok(sig)) => sig,
+            Some(Err(msg)) => {
+                warn!("[SMTools] Failed to parse signature: {0}", msg);
+                return;
+            }
+            None => {
+                warn!("[SMTools] No signature provided");
+                return;
+            }
+        };
+
+        let max_mismatches = get_integer_input("Mismatching bytes allowed", "SMTools").unwrap_or(2).max(0) as usize;
+        let max_indels = get_integer_input("Inserted/deleted bytes allowed", "SMTools").unwrap_or(0).max(0) as usize;
+        let options = FuzzyOptions { max_mismatches, max_indels };
+
+        let buf = read_view(view);
+        let now = Instant::now();
+        let matches = fuzzy_scan(&sig.bytes, &buf, &options, 20);
+        info!("[SMTools] Fuzzy scan completed in {0}ms", now.elapsed().as_millis());
+
+        if matches.len() == 0
+        {
+            warn!("[SMTools] No matches within {0} mismatches and {1} indels!", max_mismatches, max_indels);
+        }
+
+        let mut report = Report::new("SMTools: Fuzzy scan");
+        report.note(&format!("Up to {0} mismatches and {1} indels", max_mismatches, max_indels))
+            .timing("Fuzzy scan", now.elapsed());
+
+        for fuzzy_match in matches.iter() {
+            let address = fuzzy_match.offset + view.start();
+            let location = match find_address_base(view, address)
+            {
+                Ok(func) => format!("{0} + {1:#x}", func.symbol().full_name(), address - func.start()),
+                Err(msg) => format!("no func: {0}", msg)
+            };
+            report.add_match(address, &location, &format!("score {0:.1}: {1}", fuzzy_match.score,
+                fuzzy_match.diffs.iter().map(|d| d.describe()).collect::<Vec<String>>().join("; ")));
+
+            info!("[SMTools] Near-match at {0:#08X} ({1}): score {2:.1}, {3} mismatches, {4} indels",
+                address, location, fuzzy_match.score, fuzzy_match.mismatches, fuzzy_match.indels);
+            for diff in fuzzy_match.diffs.iter() {
+                info!("[SMTools]     {0}", diff.describe());
+            }
+        }
+
+        report.publish(view);
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct RepairSignatureCommand;
+
+impl Command for RepairSignatureCommand {
+    fn action(&self, view: &BinaryView) {
+        let text = match get_text_line_input("Old signature", "SMTools") {
+            Some(text) => text,
+            None => {
+                warn!("[SMTools] No signature provided");
+                return;
+            }
+        };
+
+        let buf = read_view(view);
+        let result = repair_signature(view, &buf, "signature", text.trim());
+
+        info!("[SMTools] Repair: {0}", result.status.describe());
+        if let Some(location) = result.location {
+            info!("[SMTools] Location: {0:#08X}", location);
+        }
+        for diff in result.diffs.iter() {
+            info!("[SMTools]     {0}", diff.describe());
+        }
+        if let Some(new) = &result.new {
+            log_signature(view, new);
+        }
+
+        let mut report = Report::new("SMTools: Signature repair");
+        report.note(&format!("Status: {0}", result.status.describe()))
+            .note(&format!("Before: `{0}` ({1} matches)", result.old_text, result.old_matches));
+        if let Some(new) = &result.new {
+            report.note(&format!("After: `{0}`", new.iter().map(|s| s.to_str_sm()).collect::<String>()));
+        }
+        if let Some(location) = result.location {
+            report.note(&format!("Location: {0}", address_link(location)));
+        }
+        if let Some(confidence) = result.confidence {
+            report.note(&format!("Confidence: {0:.1}", confidence));
+        }
+        if let Some(policy) = result.policy {
+            report.note(&format!("Wildcard policy: {0}", policy.name()));
+        }
+        let diffs = result.diffs.iter().map(|diff| vec![diff.describe()]).collect();
+        report.table("Differences", &["Difference"], diffs)
+            .publish(view);
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct RepairGamedataCommand;
+
+impl Command for RepairGamedataCommand {
+    fn action(&self, view: &BinaryView) {
+        let path = match get_open_filename_input("Gamedata file", "*.txt") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let gamedata = match load_gamedata(&path) {
+            Ok(gamedata) => gamedata,
+            Err(msg) => {
+                warn!("[SMTools] Failed to load gamedata: {0}", msg);
+                return;
+            }
+        };
+
+        let buf = read_view(view);
+        let now = Instant::now();
+        let results = repair_gamedata(view, &buf, &gamedata);
+        let elapsed = now.elapsed();
+        info!("[SMTools] Repair completed in {0}ms", elapsed.as_millis());
+
+        for result in results.iter() {
+            info!("[SMTools] {0}: {1}", result.name, result.status.describe());
+        }
+
+        let flagged = results.iter().filter(|r| matches!(r.status, RepairStatus::Flagged(_))).count();
+        if flagged > 0 {
+            warn!("[SMTools] {0} of {1} entries could not be placed confidently", flagged, results.len());
+        }
+
+        let table = results.iter().map(|result| vec![
+            result.name.to_owned(),
+            result.status.describe(),
+            result.location.map(address_link).unwrap_or("-".to_owned()),
+            result.confidence.map(|c| format!("{0:.1}", c)).unwrap_or("-".to_owned()),
+            result.new.as_ref().map(|new| format!("`{0}`", new.iter().map(|s| s.to_str_sm()).collect::<String>())).unwrap_or_default(),
+        ]).collect();
+        Report::new("SMTools: Gamedata repair")
+            .note(&format!("{0} of {1} entries could not be placed confidently", flagged, results.len()))
+            .table("Entries", &["Entry", "Status", "Location", "Confidence", "New signature"], table)
+            .timing("Repair", elapsed)
+            .publish(view);
+
+        let report = format_repair_report(view, &results);
+        if let Some(output) = get_save_filename_input("Repair report", "*.md", "repair.md") {
+            if let Err(err) = fs::write(&output, report) {
+                warn!("[SMTools] Failed to write report: {0}", err);
+            }
+        }
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct ApplyGamedataCommand;
+
+impl Command for ApplyGamedataCommand {
+    fn action(&self, view: &BinaryView) {
+        let path = match get_open_filename_input("Gamedata file", "*.txt") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let gamedata = match load_gamedata(&path) {
+            Ok(gamedata) => gamedata,
+            Err(msg) => {
+                warn!("[SMTools] Failed to load gamedata: {0}", msg);
+                return;
+            }
+        };
+
+        let buf = read_view(view);
+        let now = Instant::now();
+        let results = apply_gamedata(view, &buf, &gamedata);
+        view.update_analysis();
+        info!("[SMTools] Applied gamedata in {0}ms", now.elapsed().as_millis());
+        info!("[SMTools] Gamedata results:\n{0}", format_apply_table(&results));
+
+        let applied = results.iter().filter(|r| r.status == ApplyStatus::Applied).count();
+        let typed = results.iter().filter(|r| r.typed).count();
+        info!("[SMTools] Named {0} of {1} functions, {2} typed", applied, results.len(), typed);
+
+        let table = results.iter().map(|result| vec![
+            result.name.to_owned(),
+            result.address.map(address_link).unwrap_or("-".to_owned()),
+            result.status.describe(),
+            result.previous.to_owned().unwrap_or_default(),
+            if result.typed { "yes".to_owned() } else { result.type_error.to_owned().unwrap_or("no".to_owned()) },
+        ]).collect();
+        Report::new("SMTools: Apply gamedata")
+            .note(&format!("Named {0} of {1} functions, {2} typed", applied, results.len(), typed))
+            .table("Entries", &["Entry", "Address", "Status", "Previous name", "Typed"], table)
+            .timing("Apply", now.elapsed())
+            .publish(view);
+
+        let ambiguous: Vec<&str> = results.iter()
+            .filter(|r| matches!(r.status, ApplyStatus::Ambiguous(_)))
+            .map(|r| r.name.as_str())
+            .collect();
+        if ambiguous.len() > 0 {
+            warn!("[SMTools] Skipped {0} ambiguous entries: {1}", ambiguous.len(), ambiguous.join(", "));
+        }
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct LinkGamedataCommand;
+
+impl Command for LinkGamedataCommand {
+    fn action(&self, view: &BinaryView) {
+        let path = match get_open_filename_input("Gamedata file", "*.txt") {
+            Some(path) => path,
+            None => return,
+        };
+
+        match link_gamedata(view, &path) {
+            Ok(count) => info!("[SMTools] Linked {0} ({1} signatures) to {2}, verified whenever analysis of it completes", path.display(), count, library_name(view)),
+            Err(msg) => warn!("[SMTools] Failed to link gamedata: {0}", msg),
+        }
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct UnlinkGamedataCommand;
+
+impl Command for UnlinkGamedataCommand {
+    fn action(&self, view: &BinaryView) {
+        let files = linked_files(view);
+        if files.len() == 0 {
+            warn!("[SMTools] No gamedata linked to {0}", library_name(view));
+            return;
+        }
+
+        let names: Vec<&str> = files.iter().map(|f| f.as_str()).collect();
+        if let Some(choice) = get_choice_input("Unlink", "SMTools", &names) {
+            unlink_gamedata(view, &files[choice]);
+            info!("[SMTools] Unlinked {0}", files[choice]);
+        }
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct VerifyLinkedGamedataCommand;
+
+impl Command for VerifyLinkedGamedataCommand {
+    fn action(&self, view: &BinaryView) {
+        if linked_files(view).len() == 0 {
+            warn!("[SMTools] No gamedata linked to {0}", library_name(view));
+            return;
+        }
+
+        check_linked_gamedata(view);
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct SaveReportCommand;
+
+impl Command for SaveReportCommand {
+    fn action(&self, view: &BinaryView) {
+        let json = match last_report_json() {
+            Some(json) => json,
+            None => {
+                warn!("[SMTools] No report yet");
+                return;
+            }
+        };
+
+        if let Some(path) = get_save_filename_input("Report", "*.json", "report.json") {
+            if let Err(err) = fs::write(&path, json) {
+                warn!("[SMTools] Failed to write report: {0}", err);
+            }
+        }
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
+struct BuildIndexCommand;
+
+impl Command for BuildIndexCommand {
+    fn action(&self, view: &BinaryView) {
+        let buf = read_view(view);
+        let now = Instant::now();
+
+        match load_or_build_index(view, &buf)
+        {
+            Ok(index) => info!("[SMTools] Index for {0:016x} ready in {1}ms, saved to {2}", index.hash, now.elapsed().as_millis(), index_path(view).display()),
+            Err(msg) => warn!("[SMTools] Failed to build index: {0}", msg)
+        }
+    }
+    fn valid(&self, view: &BinaryView) -> bool {
+        true
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn UIPluginInit() -> bool {
     binaryninja::logger::init(LevelFilter::Trace).expect("failed to initialize logging");
+    register_settings();
+    register_linked_verification();
     register_for_address(
         "[SMT] Generate Signature (Address)",
         "Generate a signature beginning at this address",
         GenerateSignatureCommand {},
     );
     register_for_function("[SMT] Generate Signature (Function)", "Generate a signature beginning at the current function", GenerateFuncSignatureCommand {} );
+    register_for_address(
+        "[SMT] Explain Signature",
+        "Generate a signature here and show which instructions it covers and why bytes are wildcards",
+        ExplainSignatureCommand {},
+    );
+    register_for_address(
+        "[SMT] Generate Signature (Candidates)",
+        "Generate several signatures for this address and pick the most robust",
+        GenerateCandidatesCommand {},
+    );
+    register_for_function("[SMT] Generate Signature (Xref)", "Generate a signature at a call site of the current function", GenerateXrefSignatureCommand {} );
+    register_for_function("[SMT] Generate Signature (Cross-Version)", "Generate one signature for this function that is unique in several builds", GenerateCrossVersionSignatureCommand {} );
+    register_for_address(
+        "[SMT] Generate Signature (Data)",
+        "Generate a signature for an instruction referencing this global",
+        GenerateDataSignatureCommand {},
+    );
+    register_for_address(
+        "[SMT] Generate Signature (Member Offset)",
+        "Generate a signature for this instruction and extract its member displacement",
+        GenerateMemberSignatureCommand {},
+    );
+    register_for_range(
+        "[SMT] Generate Signature (Range)",
+        "Generate a signature from exactly the selected bytes",
+        GenerateRangeSignatureCommand {},
+    );
+    register("[SMT] Generate Signatures (Batch)", "Generate signatures for every function matching a pattern, tag or address list", BatchGenerateCommand {});
+    register("[SMT] Survey Signability", "Estimate how hard every function is to sign and tag the ones that can't be", SurveyCommand {});
+    register("[SMT] Manage Signatures", "List, re-verify, rename, delete or export the signatures stored in this view", ManageSignaturesCommand {});
     register("[SMT] Find Signature", "Find all matches of a signature", FindSignatureCommand {});
+    register("[SMT] Clear Match Tags", "Remove every tag left by Find Signature", ClearMatchTagsCommand {});
+    register("[SMT] Clear Signature Explanation", "Remove the highlights and comments left by Explain Signature", ClearExplanationCommand {});
+    register("[SMT] Find Signature (Fuzzy)", "Find near-matches of a broken signature", FindFuzzySignatureCommand {});
+    register("[SMT] Apply Gamedata", "Name and type the functions a gamedata file finds in this view", ApplyGamedataCommand {});
+    register("[SMT] Link Gamedata", "Re-verify a gamedata file whenever analysis of this library completes", LinkGamedataCommand {});
+    register("[SMT] Unlink Gamedata", "Stop verifying a linked gamedata file", UnlinkGamedataCommand {});
+    register("[SMT] Verify Linked Gamedata", "Verify every gamedata file linked to this library now", VerifyLinkedGamedataCommand {});
+    register("[SMT] Save Last Report (JSON)", "Save the results of the last command as JSON", SaveReportCommand {});
+    register("[SMT] Build Signature Index", "Index this view so uniqueness checks no longer scan it", BuildIndexCommand {});
+    register("[SMT] Repair Signature", "Find where a broken signature moved and regenerate it", RepairSignatureCommand {});
+    register("[SMT] Repair Gamedata", "Repair every signature of a gamedata file against this build", RepairGamedataCommand {});
     true
 }
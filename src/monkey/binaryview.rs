@@ -14,3 +14,14 @@ pub fn create_monkey_bv(func: &BinaryView) -> &MonkeyBinaryView
 
     return exposed;
 }
+
+//  The other way round, for raw views handed to us by core callbacks
+pub fn create_bv_from_monkey(monkey: &MonkeyBinaryView) -> &BinaryView
+{
+    let exposed: &BinaryView = unsafe
+    {
+        std::mem::transmute(monkey)
+    };
+
+    return exposed;
+}
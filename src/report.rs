@@ -0,0 +1,226 @@
+
+//  Report:
+//  Structured results of a command, shown through Binary Ninja's markdown report
+//  and kept so the last one can be saved as JSON for scripts.
+
+use std::ffi::CString;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use binaryninja::binaryninjacore_sys::BNShowMarkdownReport;
+use binaryninja::binaryview::BinaryView;
+use crate::monkey::binaryview::create_monkey_bv;
+use crate::settings::should_show_reports;
+use crate::signatures::dialect::Dialect;
+use crate::signatures::signature::Signature;
+
+static LAST_REPORT: Mutex<Option<String>> = Mutex::new(None);
+
+struct ReportSignature {
+    name: String,
+    address: u64,
+    signature: Signature,
+}
+
+struct ReportMatch {
+    address: u64,
+    location: String,
+    note: String,
+}
+
+struct ReportTable {
+    title: String,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+pub struct Report {
+    title: String,
+    started: Instant,
+    notes: Vec<String>,
+    signatures: Vec<ReportSignature>,
+    matches: Vec<ReportMatch>,
+    tables: Vec<ReportTable>,
+    timings: Vec<(String, u64)>,
+}
+
+//  Markdown link that navigates the view to `address`
+pub fn address_link(address: u64) -> String {
+    return format!("[{0:#08X}](binaryninja://?expr={0:#x})", address);
+}
+
+fn cell(text: &str) -> String {
+    return text.replace('|', "\\|").replace('\n', " ");
+}
+
+impl Report
+{
+    pub fn new(title: &str) -> Report {
+        return Report {
+            title: title.to_owned(),
+            started: Instant::now(),
+            notes: Vec::new(),
+            signatures: Vec::new(),
+            matches: Vec::new(),
+            tables: Vec::new(),
+            timings: Vec::new(),
+        };
+    }
+
+    pub fn note(&mut self, text: &str) -> &mut Report {
+        self.notes.push(text.to_owned());
+        return self;
+    }
+
+    pub fn signature(&mut self, name: &str, address: u64, signature: &Signature) -> &mut Report {
+        self.signatures.push(ReportSignature { name: name.to_owned(), address, signature: signature.to_owned() });
+        return self;
+    }
+
+    pub fn add_match(&mut self, address: u64, location: &str, note: &str) -> &mut Report {
+        self.matches.push(ReportMatch { address, location: location.to_owned(), note: note.to_owned() });
+        return self;
+    }
+
+    //  Free-form table; cells are used as given, so addresses should go through address_link
+    pub fn table(&mut self, title: &str, headers: &[&str], rows: Vec<Vec<String>>) -> &mut Report {
+        self.tables.push(ReportTable {
+            title: title.to_owned(),
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows,
+        });
+        return self;
+    }
+
+    pub fn timing(&mut self, label: &str, elapsed: Duration) -> &mut Report {
+        self.timings.push((label.to_owned(), elapsed.as_millis() as u64));
+        return self;
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {0}\n\n", self.title);
+
+        for note in self.notes.iter() {
+            out.push_str(&format!("- {0}\n", note));
+        }
+        if self.notes.len() > 0 {
+            out.push('\n');
+        }
+
+        if self.signatures.len() > 0 {
+            out.push_str("## Signatures\n\n| Name | Address |");
+            for dialect in Dialect::ALL.iter() {
+                out.push_str(&format!(" {0} |", dialect.label()));
+            }
+            out.push_str(" Resolve |\n|---|---|");
+            for _ in Dialect::ALL.iter() {
+                out.push_str("---|");
+            }
+            out.push_str("---|\n");
+
+            for entry in self.signatures.iter() {
+                out.push_str(&format!("| {0} | {1} |", cell(&entry.name), address_link(entry.address)));
+                for dialect in Dialect::ALL.iter() {
+                    out.push_str(&format!(" `{0}` |", dialect.format(&entry.signature.bytes)));
+                }
+                out.push_str(&format!(" {0} |\n", cell(&entry.signature.to_str_resolve())));
+            }
+            out.push('\n');
+        }
+
+        if self.matches.len() > 0 {
+            out.push_str(&format!("## Matches ({0})\n\n| Address | Location | Note |\n|---|---|---|\n", self.matches.len()));
+            for entry in self.matches.iter() {
+                out.push_str(&format!("| {0} | {1} | {2} |\n", address_link(entry.address), cell(&entry.location), cell(&entry.note)));
+            }
+            out.push('\n');
+        }
+
+        for table in self.tables.iter() {
+            out.push_str(&format!("## {0}\n\n| {1} |\n|{2}\n", table.title, table.headers.join(" | "), "---|".repeat(table.headers.len())));
+            for row in table.rows.iter() {
+                out.push_str(&format!("| {0} |\n", row.iter().map(|c| cell(c)).collect::<Vec<String>>().join(" | ")));
+            }
+            out.push('\n');
+        }
+
+        out.push_str("## Timing\n\n");
+        for (label, ms) in self.timings.iter() {
+            out.push_str(&format!("- {0}: {1}ms\n", label, ms));
+        }
+        out.push_str(&format!("- Total: {0}ms\n", self.started.elapsed().as_millis()));
+
+        return out;
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        let signatures: Vec<serde_json::Value> = self.signatures.iter().map(|entry| {
+            let mut value = serde_json::json!({
+                "name": entry.name,
+                "address": format!("{0:#x}", entry.address),
+                "resolve": entry.signature.to_str_resolve(),
+            });
+            for dialect in Dialect::ALL.iter() {
+                value[dialect.name()] = serde_json::Value::String(dialect.format(&entry.signature.bytes));
+            }
+            value
+        }).collect();
+
+        let matches: Vec<serde_json::Value> = self.matches.iter().map(|entry| serde_json::json!({
+            "address": format!("{0:#x}", entry.address),
+            "location": entry.location,
+            "note": entry.note,
+        })).collect();
+
+        let tables: Vec<serde_json::Value> = self.tables.iter().map(|table| serde_json::json!({
+            "title": table.title,
+            "headers": table.headers,
+            "rows": table.rows,
+        })).collect();
+
+        let timings: serde_json::Map<String, serde_json::Value> = self.timings.iter()
+            .map(|(label, ms)| (label.to_owned(), serde_json::json!(ms)))
+            .collect();
+
+        let report = serde_json::json!({
+            "title": self.title,
+            "notes": self.notes,
+            "signatures": signatures,
+            "matches": matches,
+            "tables": tables,
+            "timings_ms": timings,
+            "total_ms": self.started.elapsed().as_millis() as u64,
+        });
+
+        return serde_json::to_string_pretty(&report).map_err(|err| err.to_string());
+    }
+
+    //  Keep as the last report, and open it if reports are enabled for this view
+    pub fn publish(&self, view: &BinaryView) {
+        if let (Ok(json), Ok(mut last)) = (self.to_json(), LAST_REPORT.lock()) {
+            *last = Some(json);
+        }
+
+        if should_show_reports(view) {
+            show_markdown_report(view, &self.title, &self.to_markdown());
+        }
+    }
+}
+
+pub fn last_report_json() -> Option<String> {
+    return LAST_REPORT.lock().ok()?.to_owned();
+}
+
+pub fn show_markdown_report(view: &BinaryView, title: &str, markdown: &str) {
+    let (title, markdown) = match (CString::new(title), CString::new(markdown)) {
+        (Ok(title), Ok(markdown)) => (title, markdown),
+        _ => return,
+    };
+    let plaintext = markdown.to_owned();
+
+    let mBv = create_monkey_bv(view);
+    let bnBv = mBv.handle;
+
+    unsafe {
+        BNShowMarkdownReport(bnBv, title.as_ptr(), markdown.as_ptr(), plaintext.as_ptr());
+    }
+}
@@ -0,0 +1,217 @@
+
+//  Settings:
+//  Every tunable of the generators and scanners, registered under "smtools.*".
+//  Values are read with the view so per-resource (per-.bndb) overrides apply.
+
+use binaryninja::binaryview::BinaryView;
+use binaryninja::settings::Settings;
+use log::warn;
+use crate::signatures::dialect::Dialect;
+use crate::signatures::options::{GenerateOptions, Hardening, WildcardPolicy};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScanScope {
+    //  Every readable byte of the view
+    View,
+    //  Only executable segments; the rest reads as zero and never matches
+    Code,
+}
+
+const GROUP: &str = "smtools";
+
+const ITERATION_LIMIT: &str = "smtools.generate.iterationLimit";
+const WILDCARD_POLICY: &str = "smtools.generate.wildcardPolicy";
+const HARDENING: &str = "smtools.generate.hardening";
+const HARDENING_COUNT: &str = "smtools.generate.hardeningCount";
+const MINIMISE: &str = "smtools.generate.minimise";
+const MAX_MATCHES: &str = "smtools.scan.maxMatches";
+const SCAN_SCOPE: &str = "smtools.scan.scope";
+const TAG_MATCHES: &str = "smtools.scan.tagMatches";
+const SHOW_REPORT: &str = "smtools.output.showReport";
+const OUTPUT_DIALECTS: &str = "smtools.output.dialects";
+const DEFAULT_DIALECT: &str = "smtools.output.defaultDialect";
+
+pub fn register_settings() {
+    let settings = Settings::new("default");
+    settings.register_group(GROUP, "SMTools");
+
+    let schema = [
+        (ITERATION_LIMIT, r#"{
+            "title": "Iteration Limit",
+            "type": "number",
+            "default": 25,
+            "minValue": 1,
+            "maxValue": 1000,
+            "description": "Instructions the incremental generator may add before giving up."
+        }"#),
+        (WILDCARD_POLICY, r#"{
+            "title": "Wildcard Policy",
+            "type": "string",
+            "default": "pointers",
+            "enum": ["pointers", "branches", "aggressive"],
+            "enumDescriptions": [
+                "Wildcard pointer constants only.",
+                "Also wildcard relative branch displacements.",
+                "Also wildcard stack offsets."
+            ],
+            "description": "Which instruction bytes become wildcards."
+        }"#),
+        (HARDENING, r#"{
+            "title": "Hardening",
+            "type": "string",
+            "default": "distance",
+            "enum": ["instructions", "distance", "extraBytes"],
+            "enumDescriptions": [
+                "Append a fixed number of instructions once unique.",
+                "Append instructions until every other position differs in that many concrete bytes.",
+                "Append instructions until that many concrete bytes follow the point of uniqueness."
+            ],
+            "description": "How a signature is extended past the point of uniqueness."
+        }"#),
+        (HARDENING_COUNT, r#"{
+            "title": "Hardening Count",
+            "type": "number",
+            "default": 4,
+            "minValue": 0,
+            "maxValue": 64,
+            "description": "Instructions, distance or bytes used by the hardening mode."
+        }"#),
+        (MINIMISE, r#"{
+            "title": "Minimise Signatures",
+            "type": "boolean",
+            "default": true,
+            "description": "Wildcard and trim bytes that uniqueness does not depend on."
+        }"#),
+        (MAX_MATCHES, r#"{
+            "title": "Maximum Matches",
+            "type": "number",
+            "default": 50,
+            "minValue": 1,
+            "maxValue": 100000,
+            "description": "Matches listed by Find Signature before the scan stops."
+        }"#),
+        (SCAN_SCOPE, r#"{
+            "title": "Scan Scope",
+            "type": "string",
+            "default": "view",
+            "enum": ["view", "code"],
+            "enumDescriptions": [
+                "Scan every readable byte of the view.",
+                "Scan executable segments only."
+            ],
+            "description": "Which bytes uniqueness is checked against."
+        }"#),
+        (TAG_MATCHES, r#"{
+            "title": "Tag Matches",
+            "type": "boolean",
+            "default": false,
+            "description": "Tag every match of Find Signature with an \"SMTools Match\" tag holding the signature."
+        }"#),
+        (OUTPUT_DIALECTS, r#"{
+            "title": "Output Formats",
+            "type": "array",
+            "elementType": "string",
+            "default": ["generic", "sourcemod"],
+            "enum": ["generic", "sourcemod", "mask"],
+            "enumDescriptions": [
+                "Space separated bytes, ?? for wildcards.",
+                "SourceMod gamedata, \\x2A for wildcards.",
+                "Byte string and x/? mask."
+            ],
+            "description": "Formats signatures are logged in."
+        }"#),
+        (SHOW_REPORT, r#"{
+            "title": "Show Reports",
+            "type": "boolean",
+            "default": false,
+            "description": "Open a report with signatures, clickable matches and timing after each command."
+        }"#),
+        (DEFAULT_DIALECT, r#"{
+            "title": "Default Dialect",
+            "type": "string",
+            "default": "sourcemod",
+            "enum": ["generic", "sourcemod", "mask"],
+            "description": "Format used where only one is written, such as exports."
+        }"#),
+    ];
+
+    for (key, properties) in schema.iter() {
+        if !settings.register_setting_json(*key, *properties) {
+            warn!("[SMTools] Failed to register setting {0}", key);
+        }
+    }
+}
+
+fn get_integer(view: &BinaryView, key: &str) -> u64 {
+    return Settings::new("default").get_integer(key, Some(view), None);
+}
+
+fn get_string(view: &BinaryView, key: &str) -> String {
+    return Settings::new("default").get_string(key, Some(view), None).to_string();
+}
+
+fn get_bool(view: &BinaryView, key: &str) -> bool {
+    return Settings::new("default").get_bool(key, Some(view), None);
+}
+
+pub fn iteration_limit(view: &BinaryView) -> usize {
+    return get_integer(view, ITERATION_LIMIT).max(1) as usize;
+}
+
+pub fn max_matches(view: &BinaryView) -> usize {
+    return get_integer(view, MAX_MATCHES).max(1) as usize;
+}
+
+pub fn wildcard_policy(view: &BinaryView) -> WildcardPolicy {
+    let name = get_string(view, WILDCARD_POLICY);
+
+    return WildcardPolicy::ALL.into_iter()
+        .find(|policy| policy.name() == name)
+        .unwrap_or(WildcardPolicy::Pointers);
+}
+
+pub fn hardening(view: &BinaryView) -> Hardening {
+    let count = get_integer(view, HARDENING_COUNT) as usize;
+
+    return Hardening::from_name(&get_string(view, HARDENING), count);
+}
+
+pub fn generate_options(view: &BinaryView) -> GenerateOptions {
+    return GenerateOptions {
+        policy: wildcard_policy(view),
+        hardening: hardening(view),
+        minimise: get_bool(view, MINIMISE),
+    };
+}
+
+pub fn scan_scope(view: &BinaryView) -> ScanScope {
+    return match get_string(view, SCAN_SCOPE).as_str() {
+        "code" => ScanScope::Code,
+        _ => ScanScope::View,
+    };
+}
+
+pub fn should_tag_matches(view: &BinaryView) -> bool {
+    return get_bool(view, TAG_MATCHES);
+}
+
+pub fn should_show_reports(view: &BinaryView) -> bool {
+    return get_bool(view, SHOW_REPORT);
+}
+
+pub fn output_dialects(view: &BinaryView) -> Vec<Dialect> {
+    let dialects: Vec<Dialect> = Settings::new("default").get_string_list(OUTPUT_DIALECTS, Some(view), None)
+        .iter()
+        .filter_map(|name| Dialect::from_name(&name.to_string()))
+        .collect();
+
+    //  Never log nothing at all
+    if dialects.len() == 0 {
+        return vec![default_dialect(view)];
+    }
+    return dialects;
+}
+
+pub fn default_dialect(view: &BinaryView) -> Dialect {
+    return Dialect::from_name(&get_string(view, DEFAULT_DIALECT)).unwrap_or(Dialect::SourceMod);
+}
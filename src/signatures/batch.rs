@@ -0,0 +1,249 @@
+
+//  Batch:
+//  Generate signatures for many functions in one go, picked by symbol pattern,
+//  tag or address list. The view is read once and shared by every function.
+
+use binaryninja::backgroundtask::BackgroundTask;
+use binaryninja::binaryview::{BinaryView, BinaryViewExt};
+use log::{info, warn};
+use crate::gamedata::writer::{write_address_entry, write_signature_entry, KeyValuesWriter};
+use crate::signatures::index::load_or_build_index;
+use crate::signatures::linear_generate::linear_generate_signature_with;
+use crate::signatures::options::GenerateOptions;
+use crate::signatures::signature::Signature;
+use crate::signatures::xref_generate::xref_generate_signature_with;
+use crate::utils::function::{find_address_base, read_view};
+use crate::utils::tags::tagged_addresses;
+
+pub enum BatchSelection {
+    //  Glob over symbol names, eg. "CTFPlayer::*"
+    Pattern(String),
+    //  Every function or address carrying a tag of this type
+    Tag(String),
+    Addresses(Vec<u64>),
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum BatchMethod {
+    Direct,
+    Xref,
+}
+
+pub struct BatchRow {
+    pub name: String,
+    pub address: u64,
+    pub method: Option<BatchMethod>,
+    pub signature: Option<Signature>,
+    pub reason: Option<String>,
+}
+
+impl BatchMethod
+{
+    pub fn name(&self) -> &'static str {
+        match &self {
+            BatchMethod::Direct => "direct",
+            BatchMethod::Xref => "xref",
+        }
+    }
+}
+
+impl BatchRow
+{
+    pub fn succeeded(&self) -> bool {
+        return self.signature.is_some();
+    }
+}
+
+//  Glob match supporting '*' and '?'
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    return pattern[p..].iter().all(|c| *c == '*');
+}
+
+//  Addresses separated by commas or whitespace, hex with or without "0x"
+pub fn parse_address_list(text: &str) -> Result<Vec<u64>, String> {
+    let mut addresses = Vec::new();
+
+    for token in text.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| t.len() > 0) {
+        let digits = token.trim_start_matches("0x").trim_start_matches("0X");
+        match u64::from_str_radix(digits, 16) {
+            Ok(address) => addresses.push(address),
+            Err(_) => return Err(format!("'{0}' is not an address", token)),
+        }
+    }
+
+    return Ok(addresses);
+}
+
+pub fn select_targets(view: &BinaryView, selection: &BatchSelection) -> Vec<u64> {
+    let mut targets = match selection {
+        BatchSelection::Pattern(pattern) => view.functions().iter()
+            .filter(|func| {
+                let symbol = func.symbol();
+                glob_match(pattern, &symbol.full_name().to_string()) || glob_match(pattern, &symbol.raw_name().to_string())
+            })
+            .map(|func| func.start())
+            .collect(),
+        BatchSelection::Tag(tag) => tagged_addresses(view, tag),
+        BatchSelection::Addresses(addresses) => addresses.to_owned(),
+    };
+
+    targets.sort_unstable();
+    targets.dedup();
+    return targets;
+}
+
+fn generate_row(view: &BinaryView, buf: &Vec<u8>, address: u64, options: &GenerateOptions) -> BatchRow {
+    let mut row = BatchRow { name: format!("sub_{0:x}", address), address, method: None, signature: None, reason: None };
+
+    let func = match find_address_base(view, address) {
+        Ok(func) => func,
+        Err(reason) => {
+            row.reason = Some(reason.to_owned());
+            return row;
+        }
+    };
+
+    if func.start() == address {
+        row.name = func.symbol().full_name().to_string();
+    } else {
+        row.name = format!("{0}+{1:#x}", func.symbol().full_name(), address - func.start());
+    }
+
+    let direct = match linear_generate_signature_with(view, buf, address, func.to_owned(), options) {
+        Ok(bytes) => {
+            row.method = Some(BatchMethod::Direct);
+            row.signature = Some(Signature::new(bytes));
+            return row;
+        }
+        Err(reason) => reason,
+    };
+
+    //  Too short or duplicated, sign a caller instead
+    if func.start() == address {
+        match xref_generate_signature_with(view, buf, address, options) {
+            Ok(found) => {
+                row.method = Some(BatchMethod::Xref);
+                row.signature = Some(found.signature);
+                return row;
+            }
+            Err(reason) => {
+                row.reason = Some(format!("{0}; xref: {1}", direct, reason));
+                return row;
+            }
+        }
+    }
+
+    row.reason = Some(direct);
+    return row;
+}
+
+//  Generate a row for every target. Stops early, keeping what was done, if the task is cancelled.
+pub fn batch_generate(view: &BinaryView, targets: &Vec<u64>, options: &GenerateOptions, task: Option<&BackgroundTask>) -> Vec<BatchRow> {
+    let buf = read_view(view);
+    if let Err(reason) = load_or_build_index(view, &buf) {
+        warn!("[SMTools] Generating without an index, this will be slow: {0}", reason);
+    }
+
+    let mut rows = Vec::new();
+
+    for (index, address) in targets.iter().enumerate() {
+        if let Some(task) = task {
+            if task.is_cancelled() {
+                info!("[SMTools] Batch cancelled after {0} of {1} functions", index, targets.len());
+                break;
+            }
+            task.set_progress_text(format!("SMTools: generating {0}/{1} ({2:#08X})", index + 1, targets.len(), address));
+        }
+
+        rows.push(generate_row(view, &buf, *address, options));
+    }
+
+    return rows;
+}
+
+pub fn format_batch_table(rows: &Vec<BatchRow>) -> String {
+    let width = rows.iter().map(|r| r.name.len()).max().unwrap_or(0).max(4);
+    let mut table = String::new();
+
+    table.push_str(&format!("{0:<width$}  {1:<10}  {2:<6}  {3:<6}  {4}\n", "Name", "Address", "Status", "Method", "Signature / reason", width = width));
+
+    for row in rows.iter() {
+        let (status, detail) = match (&row.signature, &row.reason) {
+            (Some(signature), _) => ("ok", signature.to_str_sm()),
+            (None, Some(reason)) => ("failed", reason.to_owned()),
+            (None, None) => ("failed", String::new()),
+        };
+
+        table.push_str(&format!("{0:<width$}  {1:<#10x}  {2:<6}  {3:<6}  {4}\n",
+            row.name, row.address, status, row.method.map(|m| m.name()).unwrap_or("-"), detail, width = width));
+    }
+
+    return table;
+}
+
+//  "Signatures" for every success, "Addresses" for the ones reached through a caller,
+//  and a comment for every failure so nothing silently drops out of the file
+pub fn format_batch_gamedata(view: &BinaryView, rows: &Vec<BatchRow>) -> Result<String, String> {
+    let mut writer = KeyValuesWriter::new();
+
+    writer.open("Signatures");
+    for row in rows.iter() {
+        match (&row.signature, &row.reason) {
+            (Some(signature), _) => write_signature_entry(&mut writer, view, &row.name, signature),
+            (None, reason) => {
+                writer.comment(&format!("{0} ({1:#x}): {2}", row.name, row.address, reason.to_owned().unwrap_or_default()));
+            }
+        }
+    }
+    writer.close();
+
+    let resolved: Vec<&BatchRow> = rows.iter().filter(|r| r.method == Some(BatchMethod::Xref)).collect();
+    if resolved.len() > 0 {
+        writer.open("Addresses");
+        for row in resolved.into_iter() {
+            if let Some(signature) = &row.signature {
+                write_address_entry(&mut writer, view, &row.name, &row.name, signature)?;
+            }
+        }
+        writer.close();
+    }
+
+    return Ok(writer.finish());
+}
+
+pub fn format_batch_json(rows: &Vec<BatchRow>) -> Result<String, String> {
+    let entries: Vec<serde_json::Value> = rows.iter().map(|row| serde_json::json!({
+        "name": row.name,
+        "address": format!("{0:#x}", row.address),
+        "status": if row.succeeded() { "ok" } else { "failed" },
+        "method": row.method.map(|m| m.name()),
+        "generic": row.signature.as_ref().map(|s| s.to_str_generic()),
+        "sourcemod": row.signature.as_ref().map(|s| s.to_str_sm()),
+        "resolve": row.signature.as_ref().map(|s| s.to_str_resolve()),
+        "reason": row.reason,
+    })).collect();
+
+    return serde_json::to_string_pretty(&entries).map_err(|err| err.to_string());
+}
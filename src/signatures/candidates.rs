@@ -0,0 +1,269 @@
+
+//  Candidates:
+//  Generate several signatures for the same target from different start points,
+//  wildcard policies and hardening lengths, and rank them by how robust they look.
+
+use std::time::Instant;
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::function::Function;
+use binaryninja::interaction::show_message_box;
+use binaryninja::rc;
+use log::{info, warn};
+use crate::report::Report;
+use crate::settings::default_dialect;
+use crate::signatures::dialect::log_signature;
+use crate::signatures::instruction::analyse_instruction;
+use crate::signatures::linear_generate::{harden_signature, linear_generate_signature_with, linear_generate_unique};
+use crate::signatures::options::{GenerateOptions, Hardening, WildcardPolicy};
+use crate::signatures::sigbyte::SigByte;
+use crate::signatures::generate::signature_name;
+use crate::signatures::store::remember_signature;
+use crate::signatures::signature::{ResolveStep, Signature};
+use crate::utils::function::{find_address_base, read_view};
+use crate::utils::interaction::get_choice_input;
+
+const HARDENING_LENGTHS: [usize; 3] = [0, 1, 3];
+const LEAD_INSTRUCTIONS: usize = 3;
+
+pub struct Candidate {
+    pub signature: Signature,
+    pub start: u64,
+    pub policy: WildcardPolicy,
+    pub hardening: usize,
+    //  Bytes encoding registers or stack offsets
+    pub fragile: usize,
+    pub score: f64,
+}
+
+impl Candidate
+{
+    pub fn wildcard_ratio(&self) -> f64 {
+        if self.signature.bytes.len() == 0 {
+            return 0.0;
+        }
+        let wildcards = self.signature.bytes.iter().filter(|b| matches!(b, SigByte::Wildcard)).count();
+        return wildcards as f64 / self.signature.bytes.len() as f64;
+    }
+
+    pub fn distance(&self, target: u64) -> u64 {
+        return target - self.start;
+    }
+}
+
+//  Up to `count` instruction addresses directly before `target` in its basic block
+fn preceding_instructions(view: &BinaryView, func: &Function, target: u64, count: usize) -> Vec<u64> {
+    let blocks = view.basic_blocks_containing(target);
+    if blocks.len() != 1 {
+        return Vec::new();
+    }
+
+    let arch = func.arch();
+    let mut addresses = Vec::new();
+    let mut address = blocks.get(0).raw_start();
+
+    while address < target {
+        addresses.push(address);
+        match view.instruction_len(arch.as_ref(), address) {
+            Some(size) if size != 0 => address += size as u64,
+            _ => break,
+        }
+    }
+
+    //  Ran off an instruction boundary, so the target is mid-instruction
+    if address != target {
+        return Vec::new();
+    }
+
+    let skip = addresses.len().saturating_sub(count);
+    return addresses.into_iter().skip(skip).collect();
+}
+
+fn count_fragile(view: &BinaryView, func: &Function, start: u64, length: usize, policy: WildcardPolicy) -> usize {
+    let mut fragile = 0;
+    let mut address = start;
+
+    while address < start + length as u64 {
+        match analyse_instruction(func, view, address, policy) {
+            Ok(instruction) => {
+                fragile += instruction.fragile;
+                address += instruction.bytes.len() as u64;
+            }
+            Err(_) => break,
+        }
+    }
+
+    return fragile;
+}
+
+//  Higher is better. Short signatures with few wildcards, few register-dependent
+//  bytes and a start close to the target are the least likely to break.
+fn score(length: usize, wildcard_ratio: f64, fragile: usize, distance: u64, hardening: usize) -> f64 {
+    let mut score = 100.0;
+
+    score -= 0.5 * length as f64;
+    score -= 30.0 * wildcard_ratio;
+    score -= 2.0 * fragile as f64;
+    score -= 0.25 * distance as f64;
+    score += 2.0 * hardening.min(3) as f64;
+
+    return score;
+}
+
+pub fn generate_candidates(view: &BinaryView, target: u64, func: rc::Ref<Function>, count: usize) -> Result<Vec<Candidate>, String> {
+    let buf = read_view(view);
+
+    let mut starts = preceding_instructions(view, func.as_ref(), target, LEAD_INSTRUCTIONS);
+    starts.push(target);
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+    let mut last_error = "No candidates".to_string();
+
+    for start in starts.into_iter() {
+        for policy in WildcardPolicy::ALL {
+            let core = match linear_generate_unique(view, &buf, start, func.to_owned(), policy) {
+                Ok(core) => core,
+                Err(reason) => {
+                    last_error = reason;
+                    continue;
+                }
+            };
+
+            for hardening in HARDENING_LENGTHS {
+                let mut bytes = core.to_owned();
+                harden_signature(view, &buf, start, func.to_owned(), &mut bytes, Hardening::Instructions(hardening), policy);
+
+                let resolve = match target - start {
+                    0 => Vec::new(),
+                    distance => vec![ResolveStep::Offset(distance as i64)],
+                };
+
+                //  Short functions run out of room to harden, giving the same bytes again
+                let duplicate = candidates.iter().any(|c| c.start == start && c.signature.bytes == bytes);
+                if duplicate {
+                    continue;
+                }
+
+                let fragile = count_fragile(view, func.as_ref(), start, bytes.len(), policy);
+                let mut candidate = Candidate {
+                    signature: Signature::with_resolve(bytes, resolve),
+                    start,
+                    policy,
+                    hardening,
+                    fragile,
+                    score: 0.0,
+                };
+                candidate.score = score(candidate.signature.bytes.len(), candidate.wildcard_ratio(), fragile, candidate.distance(target), hardening);
+
+                candidates.push(candidate);
+            }
+        }
+    }
+
+    if candidates.len() == 0 {
+        return Err(last_error);
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    candidates.truncate(count);
+
+    return Ok(candidates);
+}
+
+//  Shortest signature for `target` starting at it or at one of the instructions just
+//  before it in its block, with an offset back to the target when it starts earlier
+pub fn shortest_window_signature(view: &BinaryView, buf: &Vec<u8>, target: u64, func: rc::Ref<Function>, options: &GenerateOptions) -> Result<Signature, String> {
+    let mut starts = preceding_instructions(view, func.as_ref(), target, LEAD_INSTRUCTIONS);
+    starts.push(target);
+
+    let mut best: Option<Signature> = None;
+    let mut last_error = "No start point could be signed".to_string();
+
+    for start in starts.into_iter() {
+        let bytes = match linear_generate_signature_with(view, buf, start, func.to_owned(), options) {
+            Ok(bytes) => bytes,
+            Err(reason) => {
+                last_error = reason;
+                continue;
+            }
+        };
+
+        let better = match &best {
+            Some(current) => bytes.len() < current.bytes.len(),
+            None => true,
+        };
+
+        if better {
+            let resolve = match target - start {
+                0 => Vec::new(),
+                distance => vec![ResolveStep::Offset(distance as i64)],
+            };
+            best = Some(Signature::with_resolve(bytes, resolve));
+        }
+    }
+
+    return best.ok_or(last_error);
+}
+
+pub fn generate_and_print_candidates(view: &BinaryView, offset: u64) {
+    let func = match find_address_base(view, offset) {
+        Ok(func) => func,
+        Err(reason) => {
+            warn!("[SMTools] Failed to find base! {0}", reason);
+            return;
+        }
+    };
+    let delta = offset - func.start();
+
+    let now = Instant::now();
+    let result = generate_candidates(view, offset, func.to_owned(), 5);
+    let elapsed = now.elapsed();
+    info!("[SMTools] Candidate scan completed in {0}ms", elapsed.as_millis());
+
+    let candidates = match result {
+        Ok(candidates) => candidates,
+        Err(reason) => {
+            warn!("[SMTools] Failed to get signature for '{0}' + ({1:#02x}/{1}): '{2}'", func.symbol().full_name(), delta, reason);
+            show_message_box("SMTools", &format!("Failed to get a signature: {0}", reason), binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
+            return;
+        }
+    };
+
+    let mut labels: Vec<String> = Vec::new();
+    for (index, candidate) in candidates.iter().enumerate() {
+        info!("[SMTools] #{0}: score {1:.1}, {2} bytes, {3:.0}% wildcards, {4} fragile, start -{5:#x}, {6} policy, +{7} hardening",
+            index + 1, candidate.score, candidate.signature.bytes.len(), candidate.wildcard_ratio() * 100.0,
+            candidate.fragile, candidate.distance(offset), candidate.policy.name(), candidate.hardening);
+        info!("[SMTools]     {0}", candidate.signature.to_str_generic());
+
+        labels.push(format!("#{0} ({1:.1}): {2} bytes, {3} policy, -{4:#x}", index + 1, candidate.score,
+            candidate.signature.bytes.len(), candidate.policy.name(), candidate.distance(offset)));
+    }
+
+    let choices: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+    let chosen = match get_choice_input("Signature", "SMTools", &choices) {
+        Some(index) if index < candidates.len() => &candidates[index],
+        _ => return,
+    };
+
+    info!("[SMTools] Signature for '{0}' + ({1:#02x}/{1})", func.symbol().full_name(), delta);
+    log_signature(view, &chosen.signature.bytes);
+    remember_signature(view, &signature_name(func.as_ref(), delta), offset, &chosen.signature, default_dialect(view), chosen.policy);
+
+    let rows = candidates.iter().enumerate().map(|(index, candidate)| vec![
+        (index + 1).to_string(),
+        format!("{0:.1}", candidate.score),
+        candidate.signature.bytes.len().to_string(),
+        format!("{0:.0}%", candidate.wildcard_ratio() * 100.0),
+        candidate.policy.name().to_owned(),
+        format!("`{0}`", candidate.signature.to_str_generic()),
+    ]).collect();
+
+    Report::new("SMTools: Signature candidates")
+        .signature(&signature_name(func.as_ref(), delta), offset, &chosen.signature)
+        .table("Candidates", &["#", "Score", "Bytes", "Wildcards", "Policy", "Signature"], rows)
+        .timing("Candidate scan", elapsed)
+        .publish(view);
+    for (index, step) in chosen.signature.resolve.iter().enumerate() {
+        info!("[SMTools] Resolve {0}: {1}", index + 1, step.describe());
+    }
+}
@@ -0,0 +1,159 @@
+
+//  Collision:
+//  Explain why a signature is not unique by lining every other match up
+//  against the target and showing where the code starts to differ.
+
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::function::Function;
+use log::{info, warn};
+use crate::signatures::instruction::analyse_instruction;
+use crate::signatures::options::WildcardPolicy;
+use crate::signatures::scan::find_signature;
+use crate::signatures::sigbyte::SigByte;
+use crate::utils::function::{find_address_base, find_func_end};
+
+//  How far past the start of the signature to compare
+const ALIGN_LIMIT: usize = 256;
+
+pub struct Collision {
+    pub address: u64,
+    //  Name and start of the function containing the match
+    pub function: Option<(String, u64)>,
+    //  Bytes from the start of the signature before the first difference from the target
+    pub common: usize,
+    pub target_bytes: Vec<u8>,
+    pub other_bytes: Vec<u8>,
+    //  Whether the containing functions are the same code (template instantiations, inlined copies)
+    pub identical_code: bool,
+}
+
+//  Every instruction of `func` from `offset` to its end, with the given policy applied
+pub fn function_tail_signature(view: &BinaryView, func: &Function, offset: u64, policy: WildcardPolicy) -> Vec<SigByte> {
+    let func_end = find_func_end(func);
+    let mut sig = Vec::new();
+    let mut address = offset;
+
+    while address < func_end {
+        match analyse_instruction(func, view, address, policy) {
+            Ok(mut instruction) => {
+                address += instruction.bytes.len() as u64;
+                sig.append(&mut instruction.bytes);
+            }
+            Err(_) => break,
+        }
+    }
+
+    return sig;
+}
+
+//  Compare two whole functions with pointers wildcarded
+fn functions_identical(view: &BinaryView, a: &Function, b: &Function) -> bool {
+    //  Both matches inside one function is a repeated pattern, not a copy of the function
+    if a.start() == b.start() {
+        return false;
+    }
+
+    let a_len = find_func_end(a) - a.start();
+    let b_len = find_func_end(b) - b.start();
+
+    if a_len != b_len {
+        return false;
+    }
+
+    let pattern = function_tail_signature(view, a, a.start(), WildcardPolicy::Branches);
+    let bytes = view.read_vec(b.start(), b_len as usize);
+
+    if pattern.len() != bytes.len() {
+        return false;
+    }
+
+    return pattern.iter().zip(bytes.iter()).all(|(pattern, byte)| match pattern {
+        SigByte::Wildcard => true,
+        SigByte::Match(value) => value == byte,
+    });
+}
+
+pub fn collision_report(view: &BinaryView, signature: &Vec<SigByte>, target: u64, max: usize) -> Vec<Collision> {
+    let matches = find_signature(signature, view, max + 1);
+    let target_func = find_address_base(view, target).ok();
+    let target_bytes = view.read_vec(target, ALIGN_LIMIT);
+
+    let mut collisions = Vec::new();
+
+    for address in matches.into_iter().filter(|m| *m != target).take(max) {
+        let func = find_address_base(view, address).ok();
+        let other_bytes = view.read_vec(address, ALIGN_LIMIT);
+
+        //  Inside the signature, differences under wildcards are expected
+        let common = target_bytes.iter().zip(other_bytes.iter()).enumerate()
+            .position(|(index, (a, b))| a != b && signature.get(index) != Some(&SigByte::Wildcard))
+            .unwrap_or(target_bytes.len().min(other_bytes.len()));
+
+        let identical_code = match (&target_func, &func) {
+            (Some(a), Some(b)) => functions_identical(view, a.as_ref(), b.as_ref()),
+            _ => false,
+        };
+
+        collisions.push(Collision {
+            address,
+            function: func.map(|f| (f.symbol().full_name().to_string(), f.start())),
+            common,
+            target_bytes: target_bytes.to_owned(),
+            other_bytes,
+            identical_code,
+        });
+    }
+
+    return collisions;
+}
+
+fn hex_window(bytes: &Vec<u8>, start: usize, end: usize) -> String {
+    return (start..end)
+        .map(|index| match bytes.get(index) {
+            Some(byte) => format!("{:02X} ", byte),
+            None => "   ".to_owned(),
+        })
+        .collect::<String>();
+}
+
+pub fn print_collision_report(target: u64, signature_len: usize, collisions: &Vec<Collision>) {
+    if collisions.len() == 0 {
+        return;
+    }
+
+    warn!("[SMTools] Collision report: {0} other location(s) match the signature for {1:#08X}", collisions.len(), target);
+
+    for collision in collisions.iter() {
+        let location = match &collision.function {
+            Some((name, start)) => format!("{0} + {1:#x}", name, collision.address - start),
+            None => "no func".to_owned(),
+        };
+        info!("[SMTools] {0:#08X} ({1})", collision.address, location);
+
+        if collision.common >= ALIGN_LIMIT.min(collision.other_bytes.len()) {
+            info!("[SMTools]     Identical for at least {0} bytes", collision.common);
+        }
+        else {
+            info!("[SMTools]     Diverges at +{0:#x} ({1} bytes past the end of the signature)",
+                collision.common, collision.common as i64 - signature_len as i64);
+
+            let start = collision.common.saturating_sub(8);
+            let end = collision.common + 8;
+            let marker = (start..end)
+                .map(|index| {
+                    let differs = collision.target_bytes.get(index) != collision.other_bytes.get(index);
+                    if differs { "^^ " } else { "   " }
+                })
+                .collect::<String>();
+
+            info!("[SMTools]     target +{0:#04x}: {1}", start, hex_window(&collision.target_bytes, start, end));
+            info!("[SMTools]     other  +{0:#04x}: {1}", start, hex_window(&collision.other_bytes, start, end));
+            info!("[SMTools]                   {0}", marker);
+        }
+
+        if collision.identical_code {
+            info!("[SMTools]     Containing function is identical code (template instantiation or duplicated inline?)");
+            info!("[SMTools]     No signature inside it can be unique; target it through a caller or its xrefs instead");
+        }
+    }
+}
@@ -0,0 +1,260 @@
+
+//  Cross version:
+//  Merge the same function from several builds into one signature, wildcarding
+//  every byte that differs, and check that it is unique in every build.
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::Instant;
+use binaryninja::backgroundtask::BackgroundTask;
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::function::Function;
+use binaryninja::interaction::{get_address_input, get_open_filename_input, show_message_box};
+use binaryninja::rc;
+use log::{info, warn};
+use crate::report::Report;
+use crate::settings::{default_dialect, wildcard_policy};
+use crate::signatures::dialect::log_signature;
+use crate::signatures::instruction::analyse_instruction;
+use crate::signatures::minimise::unique_prefix;
+use crate::signatures::options::WildcardPolicy;
+use crate::signatures::scan::{find_in_buffer, hamming_margin};
+use crate::signatures::sigbyte::SigByte;
+use crate::signatures::signature::Signature;
+use crate::signatures::store::remember_signature;
+use crate::utils::function::{find_address_base, find_func_end, read_view, ViewBuffer};
+
+pub struct Build {
+    pub name: String,
+    pub view: rc::Ref<BinaryView>,
+    pub func: rc::Ref<Function>,
+}
+
+pub struct MergedSignature {
+    pub bytes: Vec<SigByte>,
+    //  Hamming margin in each build, in the order the builds were given
+    pub margins: Vec<usize>,
+}
+
+//  Where the builds stop lining up: the offset and the first build that disagrees
+pub struct Divergence {
+    pub offset: u64,
+    pub build: String,
+}
+
+//  Walk the function in every build in lockstep, merging instructions until their lengths
+//  stop agreeing. Returns the merged bytes, instruction boundaries and where the builds diverged.
+fn align_builds(builds: &Vec<Build>, policy: WildcardPolicy) -> (Vec<SigByte>, Vec<usize>, Option<Divergence>) {
+    let mut merged: Vec<SigByte> = Vec::new();
+    let mut boundaries = Vec::new();
+
+    let lengths: Vec<u64> = builds.iter().map(|b| find_func_end(b.func.as_ref()) - b.func.start()).collect();
+    let shortest = lengths.into_iter().min().unwrap_or(0);
+
+    while (merged.len() as u64) < shortest {
+        let offset = merged.len() as u64;
+        let mut instruction: Option<Vec<SigByte>> = None;
+
+        for build in builds.iter() {
+            let analysed = match analyse_instruction(build.func.as_ref(), build.view.as_ref(), build.func.start() + offset, policy) {
+                Ok(analysed) => analysed.bytes,
+                Err(_) => return (merged, boundaries, None),
+            };
+
+            instruction = match instruction {
+                None => Some(analysed),
+                Some(current) => {
+                    if current.len() != analysed.len() {
+                        info!("[SMTools] Builds diverge at +{0:#x} ({1})", offset, build.name);
+                        return (merged, boundaries, Some(Divergence { offset, build: build.name.to_owned() }));
+                    }
+
+                    Some(current.iter().zip(analysed.iter())
+                        .map(|(a, b)| if a == b { *a } else { SigByte::Wildcard })
+                        .collect())
+                }
+            };
+        }
+
+        match instruction {
+            Some(mut bytes) => merged.append(&mut bytes),
+            None => break,
+        }
+        boundaries.push(merged.len());
+    }
+
+    return (merged, boundaries, None);
+}
+
+pub fn cross_version_signature(builds: &Vec<Build>, policy: WildcardPolicy) -> Result<MergedSignature, String> {
+    let (merged, boundaries, divergence) = align_builds(builds, policy);
+
+    if merged.len() == 0 {
+        return match divergence {
+            Some(divergence) => Err(format!("The builds diverge at +0x0 ({0})", divergence.build)),
+            None => Err("The builds have nothing in common at the function start".to_string()),
+        };
+    }
+
+    let buffers: Vec<ViewBuffer> = builds.iter().map(|b| read_view(b.view.as_ref())).collect();
+    let mut needed = 0;
+
+    for (build, buf) in builds.iter().zip(buffers.iter()) {
+        let target = build.func.start() - build.view.start();
+
+        match unique_prefix(&merged, buf, target) {
+            Some(length) => needed = needed.max(length),
+            None => {
+                //  Not a collision as such, the shared part just ran out
+                if let Some(divergence) = &divergence {
+                    return Err(format!("The builds diverge at +{0:#x} ({1}), before the {2} bytes shared by every build are unique in {3}",
+                        divergence.offset, divergence.build, merged.len(), build.name));
+                }
+
+                let collisions = find_in_buffer(&merged, buf, build.view.start(), 3);
+                let others: Vec<String> = collisions.iter()
+                    .filter(|c| **c != build.func.start())
+                    .map(|c| format!("{0:#08X}", c))
+                    .collect();
+                return Err(format!("No signature exists: the {0} bytes shared by every build still match {1} in {2}",
+                    merged.len(), others.join(", "), build.name));
+            }
+        }
+    }
+
+    //  Round up to a whole instruction
+    let end = boundaries.into_iter().find(|b| *b >= needed).unwrap_or(merged.len());
+    let bytes = merged[..end].to_vec();
+
+    let margins = builds.iter().zip(buffers.iter())
+        .map(|(build, buf)| hamming_margin(&bytes, buf, build.func.start() - build.view.start(), 32))
+        .collect();
+
+    return Ok(MergedSignature { bytes, margins });
+}
+
+//  Find the same function in another build, by symbol or by asking for an address
+fn locate_function(view: &BinaryView, name: &str, raw_name: &str, path: &PathBuf) -> Option<rc::Ref<Function>> {
+    if let Ok(symbol) = view.symbol_by_raw_name(raw_name) {
+        if let Ok(func) = find_address_base(view, symbol.address()) {
+            return Some(func);
+        }
+    }
+
+    let prompt = format!("Address of {0} in {1}", name, path.display());
+    let address = get_address_input(&prompt, "SMTools")?;
+
+    return find_address_base(view, address).ok();
+}
+
+//  Open each build and find the function in it. Builds where it can't be found are closed again.
+fn open_builds(view: &BinaryView, func: &Function, paths: &Vec<PathBuf>, task: &BackgroundTask) -> Vec<Build> {
+    let name = func.symbol().full_name().to_string();
+    let raw_name = func.symbol().raw_name().to_string();
+
+    let mut builds = vec![Build {
+        name: view.file().filename().to_string(),
+        view: view.to_owned(),
+        func: func.to_owned(),
+    }];
+
+    for (index, path) in paths.iter().enumerate() {
+        if task.is_cancelled() {
+            info!("[SMTools] Cross-version cancelled after opening {0} of {1} builds", index, paths.len());
+            break;
+        }
+        task.set_progress_text(format!("SMTools: opening {0} ({1}/{2})", path.display(), index + 1, paths.len()));
+
+        let other = match binaryninja::load(path.to_string_lossy().to_string()) {
+            Some(other) => other,
+            None => {
+                warn!("[SMTools] Failed to open {0}", path.display());
+                continue;
+            }
+        };
+
+        match locate_function(other.as_ref(), &name, &raw_name, path) {
+            Some(other_func) => builds.push(Build { name: path.display().to_string(), view: other.to_owned(), func: other_func }),
+            None => {
+                warn!("[SMTools] Could not find '{0}' in {1}, leaving it out", name, path.display());
+                other.file().close();
+            }
+        }
+    }
+
+    return builds;
+}
+
+pub fn generate_and_print_cross_version_signature(view: &BinaryView, func: &Function) {
+    let mut paths = Vec::new();
+    while let Some(path) = get_open_filename_input("Another build (cancel when done)", "*") {
+        paths.push(path);
+    }
+
+    if paths.len() == 0 {
+        warn!("[SMTools] Need at least one other build to compare against");
+        return;
+    }
+
+    //  Opening and analysing the other builds takes a while, so keep it off the UI thread
+    let view = view.to_owned();
+    let func = func.to_owned();
+    thread::spawn(move || {
+        let task = match BackgroundTask::new("SMTools: cross-version signature", true) {
+            Ok(task) => task,
+            Err(_) => {
+                warn!("[SMTools] Failed to start background task");
+                return;
+            }
+        };
+
+        let builds = open_builds(view.as_ref(), func.as_ref(), &paths, task.as_ref());
+        task.set_progress_text("SMTools: merging builds".to_string());
+        print_cross_version_signature(view.as_ref(), func.as_ref(), &builds);
+        task.finish();
+
+        //  The other builds were opened just for this, the first one is the user's view
+        for build in builds.iter().skip(1) {
+            build.view.file().close();
+        }
+    });
+}
+
+fn print_cross_version_signature(view: &BinaryView, func: &Function, builds: &Vec<Build>) {
+    let name = func.symbol().full_name().to_string();
+
+    if builds.len() < 2 {
+        warn!("[SMTools] Need at least one other build to compare against");
+        return;
+    }
+
+    let now = Instant::now();
+    let policy = wildcard_policy(view);
+    let result = cross_version_signature(builds, policy);
+    let elapsed = now.elapsed();
+    info!("[SMTools] Cross-version scan completed in {0}ms", elapsed.as_millis());
+
+    match result {
+        Ok(merged) => {
+            info!("[SMTools] Cross-version signature for '{0}' across {1} builds", name, builds.len());
+            log_signature(view, &merged.bytes);
+            remember_signature(view, &name, func.start(), &Signature::new(merged.bytes.to_owned()), default_dialect(view), policy);
+
+            let rows = builds.iter().zip(merged.margins.iter())
+                .map(|(build, margin)| vec![build.name.to_owned(), format!("{0:#08X}", build.func.start()), margin.to_string()])
+                .collect();
+            Report::new("SMTools: Cross-version signature")
+                .signature(&name, func.start(), &Signature::new(merged.bytes.to_owned()))
+                .table("Builds", &["Build", "Function", "Distance"], rows)
+                .timing("Cross-version scan", elapsed)
+                .publish(view);
+            for (build, margin) in builds.iter().zip(merged.margins.iter()) {
+                info!("[SMTools]     {0} @ {1:#08X}: unique, distance {2}", build.name, build.func.start(), margin);
+            }
+        }
+        Err(reason) => {
+            warn!("[SMTools] Failed to get cross-version signature for '{0}': '{1}'", name, reason);
+            show_message_box("SMTools", &reason, binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
+        }
+    }
+}
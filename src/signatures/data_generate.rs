@@ -0,0 +1,183 @@
+
+//  Data generate:
+//  Globals have no code of their own, so sign one of the instructions that
+//  reference them and record where the address lives inside that instruction.
+
+use std::time::Instant;
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::interaction::show_message_box;
+use log::{info, warn};
+use crate::gamedata::writer::format_address_gamedata;
+use crate::report::{address_link, Report};
+use crate::signatures::dialect::log_signature;
+use crate::signatures::linear_generate::linear_generate_signature_with;
+use crate::signatures::scan::find_in_buffer;
+use crate::signatures::sigbyte::SigByte;
+use crate::settings::{default_dialect, generate_options, wildcard_policy};
+use crate::signatures::store::remember_signature;
+use crate::signatures::signature::{ResolveStep, Signature};
+use crate::utils::function::{find_address_base, read_view};
+
+pub struct DataSignature {
+    pub reference: u64,
+    pub signature: Signature,
+}
+
+//  Locate the operand holding `target` in the instruction at `site`.
+//  Returns the operand's offset, width and the steps needed to extract it.
+fn find_operand(view: &BinaryView, site: u64, bytes: &Vec<u8>, target: u64) -> Option<(usize, usize, Vec<ResolveStep>)> {
+    let size = bytes.len();
+    let pointer_size = view.address_size();
+
+    //  RIP-relative: the displacement is relative to the end of the instruction,
+    //  which is not necessarily the end of the displacement (eg. trailing immediates)
+    if pointer_size == 8 && size >= 4 {
+        for index in 0..=(size - 4) {
+            let disp = i32::from_le_bytes([bytes[index], bytes[index + 1], bytes[index + 2], bytes[index + 3]]);
+            let destination = (site as i64 + size as i64 + disp as i64) as u64;
+
+            if destination == target {
+                let mut steps = vec![ResolveStep::Offset(index as i64), ResolveStep::Rel32];
+                let trailing = size - (index + 4);
+                if trailing != 0 {
+                    steps.push(ResolveStep::Offset(trailing as i64));
+                }
+                return Some((index, 4, steps));
+            }
+        }
+    }
+
+    //  Absolute: the address is encoded as-is
+    if size >= pointer_size {
+        for index in 0..=(size - pointer_size) {
+            let mut value: u64 = 0;
+            for byte in 0..pointer_size {
+                value |= (bytes[index + byte] as u64) << (byte * 8);
+            }
+
+            if value == target {
+                return Some((index, pointer_size, vec![ResolveStep::Offset(index as i64), ResolveStep::Deref(1)]));
+            }
+        }
+    }
+
+    return None;
+}
+
+pub fn data_generate_signature(view: &BinaryView, target: u64) -> Result<DataSignature, String> {
+    let refs = view.get_code_refs(target);
+
+    if refs.len() == 0 {
+        return Err("No code references to the target".to_string());
+    }
+
+    let buf = read_view(view);
+    let options = generate_options(view);
+    let mut best: Option<DataSignature> = None;
+
+    for reference in refs.iter() {
+        let site = reference.address;
+
+        let func = match find_address_base(view, site) {
+            Ok(func) => func,
+            Err(reason) => {
+                info!("[SMTools] Skipping reference at {0:#08X}: {1}", site, reason);
+                continue;
+            }
+        };
+
+        let arch = func.arch();
+        let size = match view.instruction_len(arch.as_ref(), site) {
+            Some(size) if size != 0 => size,
+            _ => {
+                info!("[SMTools] Skipping reference at {0:#08X}: invalid instruction", site);
+                continue;
+            }
+        };
+
+        let bytes = view.read_vec(site, size);
+        let (operand, width, steps) = match find_operand(view, site, &bytes, target) {
+            Some(found) => found,
+            None => {
+                info!("[SMTools] Skipping reference at {0:#08X}: no RIP-relative or absolute operand", site);
+                continue;
+            }
+        };
+
+        match linear_generate_signature_with(view, &buf, site, func.to_owned(), &options) {
+            Ok(mut sig) => {
+                //  The operand moves with every build, so never match on it
+                for index in operand..(operand + width) {
+                    if index < sig.len() {
+                        sig[index] = SigByte::Wildcard;
+                    }
+                }
+
+                //  Uniqueness was proven with the operand concrete
+                if find_in_buffer(&sig, &buf, view.start(), 2).len() != 1 {
+                    info!("[SMTools] Skipping reference at {0:#08X}: not unique with the operand wildcarded", site);
+                    continue;
+                }
+
+                let better = match &best {
+                    Some(current) => sig.len() < current.signature.bytes.len(),
+                    None => true,
+                };
+
+                if better {
+                    best = Some(DataSignature { reference: site, signature: Signature::with_resolve(sig, steps) });
+                }
+            }
+            Err(reason) => {
+                info!("[SMTools] Skipping reference at {0:#08X}: {1}", site, reason);
+            }
+        }
+    }
+
+    return match best {
+        Some(found) => Ok(found),
+        None => Err("No referencing instruction could be signed".to_string()),
+    };
+}
+
+pub fn generate_and_print_data_signature(view: &BinaryView, target: u64) {
+    if !view.offset_valid(target) {
+        warn!("[SMTools] Invalid Address");
+        return;
+    }
+
+    let name = match view.symbol_by_address(target) {
+        Ok(symbol) => symbol.full_name().to_string(),
+        Err(_) => format!("data_{0:x}", target),
+    };
+
+    let now = Instant::now();
+    let result = data_generate_signature(view, target);
+    info!("[SMTools] Data scan completed in {0}ms", now.elapsed().as_millis());
+
+    match result {
+        Ok(found) => {
+            info!("[SMTools] Data signature for '{0}' via reference at {1:#08X}", name, found.reference);
+            log_signature(view, &found.signature.bytes);
+            remember_signature(view, &name, target, &found.signature, default_dialect(view), wildcard_policy(view));
+
+            Report::new("SMTools: Data signature")
+                .note(&format!("Signed through the reference at {0}", address_link(found.reference)))
+                .signature(&name, target, &found.signature)
+                .timing("Data scan", now.elapsed())
+                .publish(view);
+            for (index, step) in found.signature.resolve.iter().enumerate() {
+                info!("[SMTools] Resolve {0}: {1}", index + 1, step.describe());
+            }
+
+            match format_address_gamedata(view, &name, &found.signature) {
+                Ok(gamedata) => info!("[SMTools] Gamedata:\n{0}", gamedata),
+                Err(reason) => warn!("[SMTools] Failed to format gamedata: {0}", reason),
+            }
+        }
+        Err(reason) => {
+            warn!("[SMTools] Failed to get data signature for '{0}': '{1}'", name, reason);
+            show_message_box("SMTools", &format!("Failed to get a data signature: {0}", reason), binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use binaryninja::binaryview::BinaryView;
+use log::info;
+use crate::settings::output_dialects;
+use crate::signatures::sigbyte::SigByte;
+
+pub use smtools_core::dialect::Dialect;
+
+//  Log a signature in every dialect enabled in settings
+pub fn log_signature(view: &BinaryView, bytes: &[SigByte]) {
+    for dialect in output_dialects(view).into_iter() {
+        info!("[SMTools] {0}: {1}", dialect.label(), dialect.format(bytes));
+    }
+}
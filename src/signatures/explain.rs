@@ -0,0 +1,209 @@
+
+//  Explain:
+//  Show which instructions a signature covers and why its bytes became wildcards,
+//  as highlights and comments in the disassembly and as a table in the report.
+
+use std::time::Instant;
+use binaryninja::binaryninjacore_sys::BNHighlightStandardColor;
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::function::Function;
+use binaryninja::interaction::show_message_box;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use crate::report::{address_link, Report};
+use crate::signatures::dialect::Dialect;
+use crate::signatures::generate::{generate_with_strategy, signature_name};
+use crate::signatures::generate_form::{default_choices, load_choices};
+use crate::signatures::instruction::{analyse_instruction, WildcardReason};
+use crate::signatures::options::WildcardPolicy;
+use crate::signatures::sigbyte::SigByte;
+use crate::utils::annotate::{get_instruction_highlight, restore_instruction_highlight, set_annotation, set_instruction_highlight, SavedHighlight};
+use crate::utils::function::{find_address_base, read_view};
+use crate::utils::metadata::{load_json, store_json};
+
+//  Instructions explained last, so the highlights and comments can be removed again
+const METADATA_KEY: &str = "smtools.explained";
+
+//  Consecutive wildcards of one instruction that share a reason
+pub struct WildcardRun {
+    pub offset: usize,
+    pub width: usize,
+    //  None when the instruction itself had no reason and the minimiser dropped the bytes
+    pub reason: Option<WildcardReason>,
+}
+
+pub struct ExplainedInstruction {
+    pub address: u64,
+    //  The part of the signature covering this instruction
+    pub bytes: Vec<SigByte>,
+    pub runs: Vec<WildcardRun>,
+}
+
+impl WildcardRun
+{
+    pub fn describe(&self) -> String {
+        let reason = match self.reason {
+            Some(reason) => reason.describe(),
+            None => "not needed for uniqueness",
+        };
+
+        if self.width == 1 {
+            return format!("byte +{0}: {1}", self.offset, reason);
+        }
+        return format!("bytes +{0}..+{1}: {2}", self.offset, self.offset + self.width - 1, reason);
+    }
+}
+
+impl ExplainedInstruction
+{
+    pub fn describe(&self) -> String {
+        return self.runs.iter().map(|run| run.describe()).collect::<Vec<String>>().join(", ");
+    }
+}
+
+fn wildcard_runs(bytes: &[SigByte], reasons: &[Option<WildcardReason>]) -> Vec<WildcardRun> {
+    let mut runs: Vec<WildcardRun> = Vec::new();
+
+    for (index, byte) in bytes.iter().enumerate() {
+        if *byte != SigByte::Wildcard {
+            continue;
+        }
+
+        let reason = reasons.get(index).copied().flatten();
+        match runs.last_mut() {
+            Some(run) if run.offset + run.width == index && run.reason == reason => run.width += 1,
+            _ => runs.push(WildcardRun { offset: index, width: 1, reason }),
+        }
+    }
+
+    return runs;
+}
+
+//  Split a signature starting at `start` back into the instructions it was built from
+pub fn explain_signature(view: &BinaryView, func: &Function, start: u64, signature: &Vec<SigByte>, policy: WildcardPolicy) -> Result<Vec<ExplainedInstruction>, String> {
+    let end = start + signature.len() as u64;
+    let mut address = start;
+    let mut explained = Vec::new();
+
+    while address < end {
+        let instruction = analyse_instruction(func, view, address, policy)?;
+        let taken = instruction.bytes.len().min((end - address) as usize);
+        let offset = (address - start) as usize;
+
+        let bytes = signature[offset..(offset + taken)].to_vec();
+        let runs = wildcard_runs(&bytes, &instruction.reasons[..taken]);
+        explained.push(ExplainedInstruction { address, bytes, runs });
+
+        address += instruction.bytes.len() as u64;
+    }
+
+    return Ok(explained);
+}
+
+//  An explained instruction and the highlight it had before
+#[derive(Serialize, Deserialize)]
+struct ExplainedAddress {
+    address: u64,
+    previous: SavedHighlight,
+}
+
+//  Remove the highlights and comments of the last explanation, returning how many instructions had them
+pub fn clear_explanation(view: &BinaryView) -> usize {
+    let explained: Vec<ExplainedAddress> = load_json(view, METADATA_KEY).unwrap_or_default();
+
+    for entry in explained.iter() {
+        if let Ok(func) = find_address_base(view, entry.address) {
+            restore_instruction_highlight(func.as_ref(), entry.address, &entry.previous);
+            set_annotation(func.as_ref(), entry.address, &[]);
+        }
+    }
+
+    store_json(view, METADATA_KEY, &Vec::<ExplainedAddress>::new());
+    return explained.len();
+}
+
+//  Highlight the covered instructions (orange where bytes were wildcarded) and comment on the wildcards
+pub fn annotate_explanation(view: &BinaryView, func: &Function, explained: &Vec<ExplainedInstruction>) {
+    clear_explanation(view);
+
+    let mut saved = Vec::new();
+    for instruction in explained.iter() {
+        saved.push(ExplainedAddress { address: instruction.address, previous: get_instruction_highlight(func, instruction.address) });
+
+        let color = match instruction.runs.len() {
+            0 => BNHighlightStandardColor::CyanHighlightColor,
+            _ => BNHighlightStandardColor::OrangeHighlightColor,
+        };
+        set_instruction_highlight(func, instruction.address, Some(color));
+
+        let lines: Vec<String> = instruction.runs.iter().map(|run| format!("wildcard {0}", run.describe())).collect();
+        set_annotation(func, instruction.address, &lines);
+    }
+
+    store_json(view, METADATA_KEY, &saved);
+}
+
+//  Generate a signature at `offset` with the last generation choices and explain it
+pub fn generate_and_explain_signature(view: &BinaryView, offset: u64) {
+    let func = match find_address_base(view, offset) {
+        Ok(func) => func,
+        Err(reason) => {
+            warn!("[SMTools] Can only explain signatures inside a function: {0}", reason);
+            return;
+        }
+    };
+
+    let choices = load_choices(view).unwrap_or_else(|| default_choices(view));
+    let options = choices.options(view);
+    let name = signature_name(func.as_ref(), offset - func.start());
+
+    //  Same strategy as the last generation, so the explanation is of the signature it gave
+    let now = Instant::now();
+    let generated = match generate_with_strategy(view, &read_view(view), offset, func.to_owned(), choices.strategy(), &options) {
+        Ok(generated) => generated,
+        Err(reason) => {
+            warn!("[SMTools] Failed to get signature for '{0}': '{1}'", name, reason);
+            show_message_box("SMTools", &format!("Failed to get a signature: {0}", reason), binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
+            return;
+        }
+    };
+    let bytes = generated.signature.bytes.to_owned();
+
+    //  Xref signatures start at a call site in another function
+    let func = match find_address_base(view, generated.start) {
+        Ok(func) => func,
+        Err(reason) => {
+            warn!("[SMTools] Failed to explain signature for '{0}': '{1}'", name, reason);
+            return;
+        }
+    };
+
+    let explained = match explain_signature(view, func.as_ref(), generated.start, &bytes, options.policy) {
+        Ok(explained) => explained,
+        Err(reason) => {
+            warn!("[SMTools] Failed to explain signature for '{0}': '{1}'", name, reason);
+            return;
+        }
+    };
+
+    annotate_explanation(view, func.as_ref(), &explained);
+
+    info!("[SMTools] Signature for '{0}' covers {1} instructions:", name, explained.len());
+    for instruction in explained.iter() {
+        info!("[SMTools]   {0:#08X}  {1:<32}  {2}", instruction.address, Dialect::Generic.format(&instruction.bytes), instruction.describe());
+    }
+
+    let wildcards = explained.iter().map(|i| i.runs.iter().map(|r| r.width).sum::<usize>()).sum::<usize>();
+    let table = explained.iter().map(|instruction| vec![
+        address_link(instruction.address),
+        format!("`{0}`", Dialect::Generic.format(&instruction.bytes)),
+        instruction.describe(),
+    ]).collect();
+
+    Report::new("SMTools: Signature explained")
+        .note(&format!("{0} instructions, {1} of {2} bytes wildcarded ({3}, {4} wildcards, {5})", explained.len(), wildcards, bytes.len(), generated.strategy.label(), options.policy.name(), options.hardening.describe()))
+        .signature(&name, offset, &generated.signature)
+        .table("Instructions", &["Address", "Bytes", "Wildcards"], table)
+        .timing("Generate and explain", now.elapsed())
+        .publish(view);
+}
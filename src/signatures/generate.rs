@@ -1,133 +1,163 @@
 use std::ops::{Deref, DerefMut};
 use std::time::Instant;
-use binaryninja::{binaryview::{BinaryView, BinaryViewExt}, function::Function, binaryninjacore_sys::{BNGetConstantsReferencedByInstructionIfAvailable, BNFreeConstantReferenceList}, interaction::show_message_box, rc};
+use binaryninja::{binaryview::{BinaryView, BinaryViewExt}, function::Function, interaction::show_message_box, rc};
 use binaryninja::binaryview::BinaryViewBase;
 use log::{warn, error, info};
 
-use crate::{monkey::{function::*, arch::create_monkey_arch}, signatures::scan::find_signature};
-use crate::signatures::linear_generate::linear_generate_signature;
-use crate::utils::function::{find_address_base, find_func_end};
+use crate::report::Report;
+use crate::signatures::scan::find_signature;
+use crate::signatures::collision::{collision_report, function_tail_signature, print_collision_report};
+use crate::signatures::data_generate::generate_and_print_data_signature;
+use crate::signatures::candidates::shortest_window_signature;
+use crate::signatures::generate_form::{GenerateChoices, Strategy};
+use crate::signatures::linear_generate::{finish_signature, linear_generate_signature_with};
+use crate::signatures::options::{GenerateOptions, WildcardPolicy};
+use crate::signatures::xref_generate::xref_generate_signature_with;
+use crate::signatures::instruction::analyse_instruction;
+use crate::settings::iteration_limit;
+use crate::signatures::signature::{ResolveStep, Signature};
+use crate::signatures::store::remember_signature;
+use crate::utils::function::{find_address_base, find_func_end, read_view};
+use crate::utils::interaction::copy_to_clipboard;
 
 use super::sigbyte::SigByte;
 
-pub fn consume_instruction<'a>(base: &Function, view: &BinaryView, offset: u64) -> Result<Vec<SigByte>, String> {
-    let arch = base.arch();
-    let arch_ref = arch.as_ref();
-    let instruction_len = view.instruction_len(arch_ref, offset);
-
-    match instruction_len {
-        Some(size) => {
-            if size == 0 {
-                return Err("Invalid instruction size".to_string());
-            }
-
-            let bytes = view.read_vec(offset, size);
-
-            let mut wildcard = 0;
-
-            {
-                let mFunc = create_monkey_function(base);
-                let bnFunc = mFunc.handle;
-
-                let mArch = create_monkey_arch(arch_ref);
-                let bnArch = mArch.0;
+pub fn consume_instruction<'a>(base: &Function, view: &BinaryView, offset: u64, policy: WildcardPolicy) -> Result<Vec<SigByte>, String> {
+    return analyse_instruction(base, view, offset, policy).map(|instruction| instruction.bytes);
+}
 
-                unsafe {
-                    let mut size: usize = 0;
-                    let constants = BNGetConstantsReferencedByInstructionIfAvailable(
-                        bnFunc, bnArch, offset, &mut size,
-                    );
+//  Name a signature after its function, with the offset when it doesn't start at the entry
+pub fn signature_name(func: &Function, delta: u64) -> String {
+    if delta == 0 {
+        return func.symbol().full_name().to_string();
+    }
+    return format!("{0}+{1:#x}", func.symbol().full_name(), delta);
+}
 
-                    for index in 0..size {
-                        let constant_ptr = constants.add(index);
-                        let constant = *constant_ptr;
+//  Generate with the choices made in the generation form, logging only the chosen dialect
+pub fn generate_and_print_signature_with(view: &BinaryView, offset: u64, choices: &GenerateChoices) {
+    if !view.offset_valid(offset) {
+        error!("[SMTools] Invalid Address");
+        return;
+    }
 
-                        //info!("Constant {0}: Size {1} Val {2} Ptr {3} Inter {4}", index, constant.size, constant.value, constant.pointer, constant.intermediate);
+    let func = match find_address_base(view, offset) {
+        Ok(func) => func,
+        Err(reason) => {
+            info!("[SMTools] Address is not within a function ({0}), generating a data signature", reason);
+            generate_and_print_data_signature(view, offset);
+            return;
+        }
+    };
 
-                        if (constant.pointer && constant.value != 0) {
-                            wildcard += constant.size;
-                        }
-                    }
+    let name = signature_name(func.as_ref(), offset - func.start());
+    let options = choices.options(view);
+    let strategy = choices.strategy();
+    let dialect = choices.dialect();
 
-                    BNFreeConstantReferenceList(constants);
-                }
-            }
+    let buf = read_view(view);
+    let now = Instant::now();
+    let result = generate_with_strategy(view, &buf, offset, func.to_owned(), strategy, &options);
+    info!("[SMTools] {0} generation completed in {1}ms", strategy.label(), now.elapsed().as_millis());
 
-            let mut sig_bytes = vec![SigByte::Wildcard; size];
-
-            if (wildcard >= size)
-            {
-                if wildcard == 8 {
-                    warn!("Narrowing 8-byte to wildcard! THIS MAY BE INVALID!");
-                    wildcard = 4;
-                }
-                else
-                {
-                    return Err(format!("Invalid instruction const parameters: Wildcard size is {0}, exceeding instruction size {1}.", wildcard, size));
-                }
-            }
+    let (used, signature) = match result {
+        Ok(generated) => (generated.strategy, generated.signature),
+        Err(reason) => {
+            warn!("[SMTools] Failed to get {0} signature for '{1}': '{2}'", strategy.label(), name, reason);
+            show_message_box("SMTools", &format!("Failed to get a signature: {0}", reason), binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
+            return;
+        }
+    };
 
-            for index in 0..(size - wildcard) {
-                //  TODO: Remove this awfulness
-                //sig_bytes.splice(index..index, vec![SigByte::Match(bytes[index])]);
-                &sig_bytes.remove(index);
-                &sig_bytes.insert(index, SigByte::Match(bytes[index]));
-            }
+    let text = dialect.format(&signature.bytes);
+    info!("[SMTools] Signature for '{0}' ({1}, {2} wildcards, {3})", name, used.label(), options.policy.name(), options.hardening.describe());
+    info!("[SMTools] {0}: {1}", dialect.label(), text);
+    if signature.resolve.len() > 0 {
+        info!("[SMTools] Resolve: {0}", signature.to_str_resolve());
+    }
+    remember_signature(view, &name, offset, &signature, dialect, options.policy);
 
-            return Ok(sig_bytes);
-        }
-        None => {
-            return Err(format!("Failure getting instruction length at {0}.", offset));
+    if choices.clipboard {
+        match copy_to_clipboard(&text) {
+            Ok(()) => info!("[SMTools] Copied to the clipboard"),
+            Err(reason) => warn!("[SMTools] Failed to copy to the clipboard: {0}", reason),
         }
     }
-}
-
 
+    Report::new("SMTools: Signature")
+        .note(&format!("{0}, {1} wildcards, hardened to {2}", used.label(), options.policy.name(), options.hardening.describe()))
+        .signature(&name, offset, &signature)
+        .timing(used.label(), now.elapsed())
+        .publish(view);
+}
 
+//  A signature made by one of the form's strategies, and the address its bytes start at
+pub struct GeneratedSignature {
+    pub strategy: Strategy,
+    pub start: u64,
+    pub signature: Signature,
+}
 
-pub fn generate_and_print_signature(view: &BinaryView, offset: u64) {
-    if (!view.offset_valid(offset)) {
-        error!("[SMTools] Invalid Address");
-    }
+fn xref_signature(view: &BinaryView, buf: &Vec<u8>, offset: u64, options: &GenerateOptions) -> Result<GeneratedSignature, String> {
+    return xref_generate_signature_with(view, buf, offset, options)
+        .map(|found| GeneratedSignature { strategy: Strategy::Xref, start: found.caller, signature: found.signature });
+}
 
-    let base = find_address_base(view, offset);
-
-    match base {
-        Ok(func) => {
-            let delta = offset - func.start();
-
-            let now = Instant::now();
-            let sig = linear_generate_signature(view, offset, func.to_owned());
-            info!("[SMTools] Linear scan completed in {0}ms", now.elapsed().as_millis());
-            match sig
-            {
-                Ok(signature) => {
-
-                    info!("[SMTools] Signature for '{0}' + ({1:#02x}/{1})", func.symbol().full_name(), delta);
-                    info!("[SMTools] Generic: {0}", signature.to_owned().into_iter().map(|s| s.to_str_generic() ).collect::<String>());
-                    info!("[SMTools] Sourcemod: {0}", signature.to_owned().into_iter().map(|s| s.to_str_sm() ).collect::<String>());
-                }
-                Err(reason) =>
-                    {
-                        warn!("[SMTools] Failed to get signature for '{0}' + ({1:#02x}/{1}): '{2}'", func.symbol().full_name(), delta, reason);
-                    }
+//  Run a strategy at `offset`. If it fails, report what the rest of the function still
+//  collides with, and at a function start fall back to signing a call site instead.
+pub fn generate_with_strategy(view: &BinaryView, buf: &Vec<u8>, offset: u64, func: rc::Ref<Function>, strategy: Strategy, options: &GenerateOptions) -> Result<GeneratedSignature, String> {
+    let delta = offset - func.start();
+
+    let result = match strategy {
+        Strategy::Linear => linear_generate_signature_with(view, buf, offset, func.to_owned(), options)
+            .map(|bytes| GeneratedSignature { strategy, start: offset, signature: Signature::new(bytes) }),
+        Strategy::Incremental => generate_signature(view, offset, func.to_owned(), options.policy)
+            .map(|bytes| GeneratedSignature { strategy, start: offset, signature: Signature::new(finish_signature(view, buf, offset, func.to_owned(), bytes, options)) }),
+        Strategy::ShortestWindow => shortest_window_signature(view, buf, offset, func.to_owned(), options)
+            .map(|signature| {
+                //  Windows starting early resolve forward to the address
+                let start = match signature.resolve.first() {
+                    Some(ResolveStep::Offset(distance)) => offset - *distance as u64,
+                    _ => offset,
+                };
+                GeneratedSignature { strategy, start, signature }
+            }),
+        //  Call sites lead to the function entry, not into the middle of it
+        Strategy::Xref if delta != 0 => Err(format!("Cross-reference signatures resolve to a function start, {0:#x} is {1:#x} into '{2}'", offset, delta, func.symbol().full_name())),
+        Strategy::Xref => xref_signature(view, buf, offset, options),
+    };
+
+    return match result {
+        Err(reason) if strategy != Strategy::Xref => {
+            warn!("[SMTools] Failed to get {0} signature for '{1}': '{2}'", strategy.label(), signature_name(func.as_ref(), delta), reason);
+
+            //  Explain what the rest of the function still collides with
+            let remainder = function_tail_signature(view, func.as_ref(), offset, options.policy);
+            let collisions = collision_report(view, &remainder, offset, 10);
+            print_collision_report(offset, remainder.len(), &collisions);
+
+            //  Thunks and small wrappers can often be found through their callers instead
+            if delta == 0 {
+                info!("[SMTools] Falling back to a cross-reference signature");
+                xref_signature(view, buf, offset, options)
+            }
+            else {
+                Err(reason)
             }
-            
-        }
-        Err(reason) => {
-            error!("[SMTools] Failed to find base! {0}", reason);
         }
-    }
+        result => result,
+    };
 }
 
 pub fn generate_signature(
     view: &BinaryView,
     offset: u64,
     func: rc::Ref<Function>,
+    policy: WildcardPolicy,
 ) -> Result<Vec<SigByte>, String> {
     let mut func_end = find_func_end(&func);
 
-    let mut first =  consume_instruction(func.as_ref(), view, offset);
+    let mut first =  consume_instruction(func.as_ref(), view, offset, policy);
     if let Err(msg) = &first
     {
         return Err(msg.to_owned());
@@ -135,6 +165,7 @@ pub fn generate_signature(
     let mut signature: &mut Vec<SigByte> = &mut first.expect("Impossible error");
 
     let mut iter = 0;
+    let limit = iteration_limit(view);
 
     while find_signature(&signature, view, 2).len() == 2 {
         //  This crashes if we read the padding after a function
@@ -143,7 +174,6 @@ pub fn generate_signature(
         /*func.highest_address()*/
         func_end <= (offset + (signature.len() as u64)) {
             warn!("[SMTools] HIT FUNC LIMIT");
-            show_message_box("SMTools", "There was not enough unique bytes left in the function to create a signature.", binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
             return Err("Not enough unique bytes in the remainder of the subroutine".to_string());
         }
 
@@ -151,7 +181,7 @@ pub fn generate_signature(
         //info!("[SMTools] Sig {:?}", signature);
 
         let pointer = offset + (signature.len() as u64);
-        let mut instruction = consume_instruction(func.as_ref(), view, pointer);
+        let mut instruction = consume_instruction(func.as_ref(), view, pointer, policy);
 
         if let Err(msg) = instruction.as_ref()
         {
@@ -163,10 +193,9 @@ pub fn generate_signature(
 
         iter = iter + 1;
 
-        if iter >= 25 {
+        if iter >= limit {
             warn!("[SMTools] HIT ITER LIMIT");
-            show_message_box("SMTools", "Hit scan iteration limit before finding a unique signature.", binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
-            return Err("Hit iteration limit".to_string());
+            return Err("Hit the scan iteration limit before finding a unique signature".to_string());
         }
     }
 
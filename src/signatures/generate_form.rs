@@ -0,0 +1,156 @@
+
+//  Generate form:
+//  The choices asked for before generating a signature. The last choices are kept in
+//  the view's metadata so the form opens with them next time.
+
+use binaryninja::binaryview::BinaryView;
+use binaryninja::interaction::{FormInputBuilder, FormResponses};
+use serde::{Deserialize, Serialize};
+use crate::settings::{default_dialect, generate_options};
+use crate::signatures::dialect::Dialect;
+use crate::signatures::options::{GenerateOptions, Hardening, WildcardPolicy};
+use crate::utils::metadata::{load_json, store_json};
+
+const METADATA_KEY: &str = "smtools.generateForm";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Strategy {
+    //  Linear scan from the address, hardened and minimised
+    Linear,
+    //  Add one instruction at a time and rescan (the original generator)
+    Incremental,
+    //  Shortest signature starting at or just before the address
+    ShortestWindow,
+    //  Sign a call site and follow the call
+    Xref,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GenerateChoices {
+    pub dialect: String,
+    pub policy: String,
+    pub strategy: String,
+    pub hardening: String,
+    pub margin: usize,
+    pub clipboard: bool,
+}
+
+impl Strategy
+{
+    pub const ALL: [Strategy; 4] = [Strategy::Linear, Strategy::Incremental, Strategy::ShortestWindow, Strategy::Xref];
+
+    pub fn name(&self) -> &'static str {
+        match &self {
+            Strategy::Linear => "linear",
+            Strategy::Incremental => "incremental",
+            Strategy::ShortestWindow => "shortestWindow",
+            Strategy::Xref => "xref",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match &self {
+            Strategy::Linear => "Linear",
+            Strategy::Incremental => "Incremental",
+            Strategy::ShortestWindow => "Shortest window",
+            Strategy::Xref => "Xref",
+        }
+    }
+}
+
+impl GenerateChoices
+{
+    pub fn dialect(&self) -> Dialect {
+        return Dialect::from_name(&self.dialect).unwrap_or(Dialect::SourceMod);
+    }
+
+    pub fn policy(&self) -> WildcardPolicy {
+        return WildcardPolicy::ALL.into_iter()
+            .find(|policy| policy.name() == self.policy)
+            .unwrap_or(WildcardPolicy::Pointers);
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        return Strategy::ALL.into_iter()
+            .find(|strategy| strategy.name() == self.strategy)
+            .unwrap_or(Strategy::Linear);
+    }
+
+    pub fn hardening(&self) -> Hardening {
+        return Hardening::from_name(&self.hardening, self.margin);
+    }
+
+    //  Generator options for these choices; minimising still follows settings
+    pub fn options(&self, view: &BinaryView) -> GenerateOptions {
+        return GenerateOptions {
+            policy: self.policy(),
+            hardening: self.hardening(),
+            minimise: generate_options(view).minimise,
+        };
+    }
+}
+
+//  What the form starts with before anything was chosen for this view
+pub fn default_choices(view: &BinaryView) -> GenerateChoices {
+    let options = generate_options(view);
+
+    return GenerateChoices {
+        dialect: default_dialect(view).name().to_owned(),
+        policy: options.policy.name().to_owned(),
+        strategy: Strategy::Linear.name().to_owned(),
+        hardening: options.hardening.name().to_owned(),
+        margin: options.hardening.count(),
+        clipboard: false,
+    };
+}
+
+pub fn load_choices(view: &BinaryView) -> Option<GenerateChoices> {
+    return load_json(view, METADATA_KEY);
+}
+
+pub fn save_choices(view: &BinaryView, choices: &GenerateChoices) {
+    store_json(view, METADATA_KEY, choices);
+}
+
+//  Show the generation form, pre-filled with the last choices for this view.
+//  Returns None if it was cancelled.
+pub fn ask_generate_choices(view: &BinaryView) -> Option<GenerateChoices> {
+    let last = load_choices(view).unwrap_or_else(|| default_choices(view));
+
+    let dialects: Vec<&str> = Dialect::ALL.iter().map(|d| d.label()).collect();
+    let policies: Vec<&str> = WildcardPolicy::ALL.iter().map(|p| p.name()).collect();
+    let strategies: Vec<&str> = Strategy::ALL.iter().map(|s| s.label()).collect();
+
+    let responses = FormInputBuilder::new()
+        .choice_field("Format", &dialects, Dialect::ALL.iter().position(|d| *d == last.dialect()))
+        .choice_field("Wildcards", &policies, WildcardPolicy::ALL.iter().position(|p| *p == last.policy()))
+        .choice_field("Strategy", &strategies, Strategy::ALL.iter().position(|s| *s == last.strategy()))
+        .choice_field("Hardening", &Hardening::NAMES, Hardening::NAMES.iter().position(|h| *h == last.hardening().name()))
+        .integer_field("Hardening margin", Some(last.margin as i64))
+        .checkbox_field("Copy to clipboard", Some(last.clipboard))
+        .get_form_input("SMTools: Generate Signature");
+
+    let index = |field: usize| match responses.get(field) {
+        Some(FormResponses::Index(index)) => Some(*index),
+        _ => None,
+    };
+
+    let choices = GenerateChoices {
+        dialect: Dialect::ALL[index(0)?].name().to_owned(),
+        policy: WildcardPolicy::ALL[index(1)?].name().to_owned(),
+        strategy: Strategy::ALL[index(2)?].name().to_owned(),
+        hardening: Hardening::NAMES[index(3)?].to_owned(),
+        margin: match responses.get(4) {
+            Some(FormResponses::Integer(margin)) => (*margin).clamp(0, 64) as usize,
+            _ => last.margin,
+        },
+        clipboard: match responses.get(5) {
+            Some(FormResponses::Integer(checked)) => *checked != 0,
+            _ => last.clipboard,
+        },
+    };
+
+    save_choices(view, &choices);
+
+    return Some(choices);
+}
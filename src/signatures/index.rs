@@ -0,0 +1,331 @@
+
+//  Index:
+//  Suffix array over the bytes of a view, sorted to a fixed depth, so the number of
+//  occurrences of a concrete run can be found with a binary search instead of a scan.
+//  Signatures with wildcards are split into concrete runs whose positions are intersected.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use binaryninja::binaryview::{BinaryView, BinaryViewExt};
+use log::{info, warn};
+use crate::signatures::sigbyte::SigByte;
+use crate::utils::function::buffer_info;
+use smtools_core::scan::near_matches;
+
+//  Suffixes are only sorted by this many leading bytes; longer runs are verified directly
+const DEPTH: usize = 64;
+const MAGIC: &[u8; 8] = b"SMTIDX1\0";
+
+static INDEX_CACHE: Mutex<Vec<Arc<SignatureIndex>>> = Mutex::new(Vec::new());
+
+pub struct SignatureIndex {
+    pub hash: u64,
+    data: Vec<u8>,
+    suffixes: Vec<u32>,
+}
+
+//  FNV-1a, stable across builds and platforms unlike DefaultHasher
+pub fn content_hash(buf: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in buf.iter() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    return hash;
+}
+
+fn build_suffixes(data: &Vec<u8>) -> Vec<u32> {
+    let n = data.len();
+    let mut suffixes: Vec<u32> = (0..n as u32).collect();
+    let mut rank: Vec<u32> = data.iter().map(|b| *b as u32).collect();
+    let mut next = vec![0u32; n];
+    let mut k = 1;
+
+    //  Prefix doubling: after each round suffixes are sorted by their first 2k bytes
+    while k < DEPTH {
+        let key = |i: u32, rank: &Vec<u32>| -> u64 {
+            let i = i as usize;
+            let second = if i + k < n { rank[i + k] as u64 + 1 } else { 0 };
+            return ((rank[i] as u64) << 32) | second;
+        };
+
+        suffixes.sort_unstable_by_key(|i| key(*i, &rank));
+
+        let mut current = 0;
+        for j in 0..n {
+            if j > 0 && key(suffixes[j], &rank) != key(suffixes[j - 1], &rank) {
+                current += 1;
+            }
+            next[suffixes[j] as usize] = current;
+        }
+        std::mem::swap(&mut rank, &mut next);
+
+        //  Every suffix is already distinct
+        if current as usize == n.saturating_sub(1) {
+            break;
+        }
+        k *= 2;
+    }
+
+    return suffixes;
+}
+
+impl SignatureIndex
+{
+    pub fn build(buf: &Vec<u8>) -> SignatureIndex {
+        return SignatureIndex {
+            hash: content_hash(buf),
+            data: buf.to_owned(),
+            suffixes: build_suffixes(buf),
+        };
+    }
+
+    fn compare(&self, suffix: u32, pattern: &[u8]) -> std::cmp::Ordering {
+        let start = suffix as usize;
+        let end = (start + pattern.len()).min(self.data.len());
+        return self.data[start..end].cmp(pattern);
+    }
+
+    //  Range of suffixes starting with `pattern` (at most DEPTH bytes)
+    fn range(&self, pattern: &[u8]) -> (usize, usize) {
+        let pattern = &pattern[..pattern.len().min(DEPTH)];
+        let lower = self.suffixes.partition_point(|s| self.compare(*s, pattern) == std::cmp::Ordering::Less);
+        let upper = self.suffixes.partition_point(|s| self.compare(*s, pattern) != std::cmp::Ordering::Greater);
+        return (lower, upper);
+    }
+
+    //  How many times a concrete run of bytes occurs, in O(m log n)
+    pub fn count_prefix(&self, pattern: &[u8]) -> usize {
+        if pattern.len() <= DEPTH {
+            let (lower, upper) = self.range(pattern);
+            return upper - lower;
+        }
+        return self.positions(pattern).len();
+    }
+
+    //  Sorted offsets of a concrete run of bytes
+    pub fn positions(&self, pattern: &[u8]) -> Vec<u64> {
+        let (lower, upper) = self.range(pattern);
+        let mut found: Vec<u64> = self.suffixes[lower..upper].iter()
+            .filter(|s| pattern.len() <= DEPTH || self.data[(**s as usize)..].starts_with(pattern))
+            .map(|s| *s as u64)
+            .collect();
+        found.sort_unstable();
+        return found;
+    }
+
+    fn matches_at(&self, signature: &Vec<SigByte>, offset: u64) -> bool {
+        let start = offset as usize;
+        if start + signature.len() > self.data.len() {
+            return false;
+        }
+
+        return signature.iter().enumerate().all(|(index, byte)| match byte {
+            SigByte::Wildcard => true,
+            SigByte::Match(value) => *value == self.data[start + index],
+        });
+    }
+
+    //  Buffer offsets where the signature matches, up to `max`
+    pub fn find(&self, signature: &Vec<SigByte>, max: usize) -> Vec<u64> {
+        //  Concrete runs, as (offset into the signature, bytes)
+        let mut runs: Vec<(usize, Vec<u8>)> = Vec::new();
+        for (index, byte) in signature.iter().enumerate() {
+            match byte {
+                SigByte::Match(value) => match runs.last_mut() {
+                    Some((start, bytes)) if *start + bytes.len() == index => bytes.push(*value),
+                    _ => runs.push((index, vec![*value])),
+                },
+                SigByte::Wildcard => {}
+            }
+        }
+
+        if runs.len() == 0 {
+            if self.data.len() < signature.len() {
+                return Vec::new();
+            }
+            return (0..=(self.data.len() - signature.len()) as u64).take(max).collect();
+        }
+
+        //  Start from the rarest run and intersect with the others while that is cheaper than checking
+        runs.sort_by_key(|(_, bytes)| self.count_prefix(bytes));
+
+        let (anchor, bytes) = &runs[0];
+        let mut candidates: Vec<u64> = self.positions(bytes).into_iter()
+            .filter(|p| *p >= *anchor as u64)
+            .map(|p| p - *anchor as u64)
+            .collect();
+
+        for (offset, bytes) in runs.iter().skip(1) {
+            if self.count_prefix(bytes) > candidates.len() * 4 {
+                break;
+            }
+
+            let shifted: Vec<u64> = self.positions(bytes).into_iter()
+                .filter(|p| *p >= *offset as u64)
+                .map(|p| p - *offset as u64)
+                .collect();
+
+            let mut intersected = Vec::new();
+            let (mut a, mut b) = (0, 0);
+            while a < candidates.len() && b < shifted.len() {
+                if candidates[a] < shifted[b] { a += 1; }
+                else if candidates[a] > shifted[b] { b += 1; }
+                else {
+                    intersected.push(candidates[a]);
+                    a += 1;
+                    b += 1;
+                }
+            }
+            candidates = intersected;
+        }
+
+        return candidates.into_iter()
+            .filter(|c| self.matches_at(signature, *c))
+            .take(max)
+            .collect();
+    }
+
+    //  Same as scan::near_matches. With k mismatches allowed, splitting the concrete bytes
+    //  into k+1 pieces means at least one piece matches exactly, so only piece hits are checked.
+    pub fn near(&self, signature: &Vec<SigByte>, max_mismatches: usize) -> Vec<(u64, Vec<usize>)> {
+        let concrete: Vec<usize> = signature.iter().enumerate()
+            .filter(|(_, b)| matches!(b, SigByte::Match(_)))
+            .map(|(index, _)| index)
+            .collect();
+
+        if signature.len() == 0 || self.data.len() < signature.len() {
+            return Vec::new();
+        }
+        if concrete.len() <= max_mismatches {
+            return near_matches(signature, &self.data, max_mismatches);
+        }
+
+        let pieces = max_mismatches + 1;
+        let piece_len = concrete.len() / pieces;
+        let mut candidates: BTreeSet<u64> = BTreeSet::new();
+
+        for piece in 0..pieces {
+            let first = piece * piece_len;
+            let last = if piece == pieces - 1 { concrete.len() } else { first + piece_len };
+
+            let mut masked = vec![SigByte::Wildcard; signature.len()];
+            for index in concrete[first..last].iter() {
+                masked[*index] = signature[*index];
+            }
+            candidates.extend(self.find(&masked, usize::MAX));
+        }
+
+        let mut found = Vec::new();
+        for address in candidates.into_iter() {
+            let start = address as usize;
+            let mismatches: Vec<usize> = concrete.iter()
+                .filter(|index| signature[**index] != SigByte::Match(self.data[start + **index]))
+                .copied()
+                .collect();
+
+            if mismatches.len() <= max_mismatches {
+                found.push((address, mismatches));
+            }
+        }
+
+        return found;
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = fs::File::create(path).map_err(|err| err.to_string())?;
+        let mut writer = BufWriter::new(file);
+
+        let mut write = |bytes: &[u8]| writer.write_all(bytes).map_err(|err| err.to_string());
+        write(MAGIC)?;
+        write(&self.hash.to_le_bytes())?;
+        write(&(DEPTH as u32).to_le_bytes())?;
+        write(&(self.suffixes.len() as u64).to_le_bytes())?;
+        for suffix in self.suffixes.iter() {
+            write(&suffix.to_le_bytes())?;
+        }
+
+        return Ok(());
+    }
+
+    //  Load a saved index, if it was built for exactly these bytes
+    pub fn load(path: &Path, buf: &Vec<u8>) -> Result<SignatureIndex, String> {
+        let raw = fs::read(path).map_err(|err| err.to_string())?;
+
+        if raw.len() < 28 || &raw[0..8] != MAGIC {
+            return Err("Not an SMTools index".to_string());
+        }
+
+        let read_u64 = |at: usize| u64::from_le_bytes(raw[at..at + 8].try_into().unwrap());
+        let hash = read_u64(8);
+        let depth = u32::from_le_bytes(raw[16..20].try_into().unwrap()) as usize;
+        let count = read_u64(20) as usize;
+
+        if depth != DEPTH || count != buf.len() || raw.len() != 28 + count * 4 {
+            return Err("Index was built with different parameters".to_string());
+        }
+        if hash != content_hash(buf) {
+            return Err("Index is for different contents".to_string());
+        }
+
+        let suffixes = raw[28..].chunks_exact(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+
+        return Ok(SignatureIndex { hash, data: buf.to_owned(), suffixes });
+    }
+}
+
+pub fn index_path(view: &BinaryView) -> PathBuf {
+    return PathBuf::from(format!("{0}.smtidx", view.file().filename()));
+}
+
+//  The index for this buffer, if one has been built or loaded. Only buffers from
+//  read_view are looked up, by the hash taken while reading them.
+pub fn cached_index(buf: &Vec<u8>) -> Option<Arc<SignatureIndex>> {
+    let info = buffer_info(buf)?;
+    let cache = INDEX_CACHE.lock().ok()?;
+
+    return cache.iter()
+        .find(|index| index.hash == info.hash && index.data.len() == buf.len())
+        .cloned();
+}
+
+pub fn load_or_build_index(view: &BinaryView, buf: &Vec<u8>) -> Result<Arc<SignatureIndex>, String> {
+    if let Some(index) = cached_index(buf) {
+        return Ok(index);
+    }
+
+    if buf.len() > u32::MAX as usize {
+        return Err("View is too large to index".to_string());
+    }
+
+    let path = index_path(view);
+    let index = match SignatureIndex::load(&path, buf) {
+        Ok(index) => {
+            info!("[SMTools] Loaded index from {0}", path.display());
+            index
+        }
+        Err(reason) => {
+            if path.exists() {
+                info!("[SMTools] Rebuilding index: {0}", reason);
+            }
+
+            let index = SignatureIndex::build(buf);
+            if let Err(reason) = index.save(&path) {
+                warn!("[SMTools] Failed to save index to {0}: {1}", path.display(), reason);
+            }
+            index
+        }
+    };
+
+    let index = Arc::new(index);
+    let mut cache = INDEX_CACHE.lock().map_err(|_| "Index cache is poisoned".to_string())?;
+    cache.retain(|cached| cached.hash != index.hash);
+    cache.push(index.to_owned());
+
+    return Ok(index);
+}
@@ -0,0 +1,208 @@
+use binaryninja::architecture::Architecture;
+use binaryninja::binaryview::{BinaryView, BinaryViewExt};
+use binaryninja::disassembly::InstructionTextTokenContents;
+use binaryninja::function::Function;
+use log::warn;
+use crate::signatures::options::WildcardPolicy;
+use crate::signatures::sigbyte::SigByte;
+use crate::utils::function::{instruction_constants, relocation_ranges};
+
+//  Why a byte of an instruction was turned into a wildcard
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WildcardReason {
+    //  The loader relocates these bytes
+    Relocation,
+    //  Analysis found a pointer constant in the instruction
+    Pointer,
+    //  Relative call/jmp/jcc displacement
+    Branch,
+    //  Displacement from the stack or frame pointer
+    Stack,
+}
+
+impl WildcardReason
+{
+    pub fn describe(&self) -> &'static str {
+        match &self {
+            WildcardReason::Relocation => "relocation",
+            WildcardReason::Pointer => "pointer constant",
+            WildcardReason::Branch => "branch displacement",
+            WildcardReason::Stack => "stack offset",
+        }
+    }
+}
+
+pub struct InstructionBytes {
+    pub address: u64,
+    pub bytes: Vec<SigByte>,
+    pub reasons: Vec<Option<WildcardReason>>,
+    //  Bytes which encode registers or stack offsets, and so shift with register allocation
+    pub fragile: usize,
+}
+
+//  Every offset/width at which `value` is encoded little-endian in `bytes`,
+//  preferring the widest encoding at any one position
+pub fn find_encoded(bytes: &Vec<u8>, value: i64) -> Vec<(usize, usize)> {
+    let mut found: Vec<(usize, usize)> = Vec::new();
+
+    for width in [4usize, 2, 1] {
+        let fits = match width {
+            1 => value >= i8::MIN as i64 && value <= i8::MAX as i64,
+            2 => value >= i16::MIN as i64 && value <= i16::MAX as i64,
+            _ => value >= i32::MIN as i64 && value <= i32::MAX as i64,
+        };
+        if !fits || bytes.len() < width {
+            continue;
+        }
+
+        let encoded = &value.to_le_bytes()[0..width];
+
+        for index in 0..=(bytes.len() - width) {
+            if &bytes[index..(index + width)] != encoded {
+                continue;
+            }
+
+            //  0x10 also "matches" 10 00 00 00 as a disp8
+            let covered = found.iter().any(|(offset, size)| index >= *offset && index + width <= offset + size);
+            if !covered {
+                found.push((index, width));
+            }
+        }
+    }
+
+    return found;
+}
+
+//  Size of the rel8/rel32 at the end of a relative branch, if this is one
+fn branch_displacement(bytes: &Vec<u8>) -> Option<usize> {
+    match bytes.as_slice() {
+        //  call/jmp rel32
+        [0xE8, ..] | [0xE9, ..] => Some(4),
+        //  jmp rel8, jcc rel8, loop/jecxz rel8
+        [0xEB, ..] | [0x70..=0x7F, ..] | [0xE0..=0xE3, ..] => Some(1),
+        //  jcc rel32
+        [0x0F, 0x80..=0x8F, ..] => Some(4),
+        _ => None,
+    }
+}
+
+//  Read the disassembly to find stack displacements and count register operands
+fn stack_operands(base: &Function, bytes: &Vec<u8>, offset: u64) -> (Vec<i64>, usize) {
+    let arch = base.arch();
+    let mut displacements = Vec::new();
+    let mut registers = 0;
+
+    if let Some((_, tokens)) = arch.instruction_text(bytes.as_slice(), offset) {
+        let mut in_memory = false;
+        let mut stack_based = false;
+
+        for token in tokens.iter() {
+            match token.contents() {
+                InstructionTextTokenContents::BeginMemoryOperand => {
+                    in_memory = true;
+                    stack_based = false;
+                }
+                InstructionTextTokenContents::EndMemoryOperand => in_memory = false,
+                InstructionTextTokenContents::Register => {
+                    registers += 1;
+                    let name = token.text().to_string();
+                    if in_memory && ["esp", "ebp", "rsp", "rbp"].contains(&name.as_str()) {
+                        stack_based = true;
+                    }
+                }
+                InstructionTextTokenContents::Integer(value) => {
+                    if in_memory && stack_based {
+                        displacements.push(value as i64);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    //  Registers are packed into the ModRM/SIB bytes, so count at most one per instruction
+    return (displacements, registers.min(1));
+}
+
+pub fn analyse_instruction(base: &Function, view: &BinaryView, offset: u64, policy: WildcardPolicy) -> Result<InstructionBytes, String> {
+    let arch = base.arch();
+    let size = match view.instruction_len(arch.as_ref(), offset) {
+        Some(size) => size,
+        None => return Err(format!("Failure getting instruction length at {0}.", offset)),
+    };
+
+    if size == 0 {
+        return Err("Invalid instruction size".to_string());
+    }
+
+    let bytes = view.read_vec(offset, size);
+    let mut reasons: Vec<Option<WildcardReason>> = vec![None; size];
+
+    //  Pointer constants are assumed to sit at the end of the instruction
+    let mut wildcard = 0;
+
+    for constant in instruction_constants(base, offset).into_iter() {
+        //info!("Constant: Size {0} Val {1} Ptr {2} Inter {3}", constant.size, constant.value, constant.pointer, constant.intermediate);
+
+        if (constant.pointer && constant.value != 0) {
+            wildcard += constant.size;
+        }
+    }
+
+    if (wildcard >= size)
+    {
+        if wildcard == 8 {
+            warn!("Narrowing 8-byte to wildcard! THIS MAY BE INVALID!");
+            wildcard = 4;
+        }
+        else
+        {
+            return Err(format!("Invalid instruction const parameters: Wildcard size is {0}, exceeding instruction size {1}.", wildcard, size));
+        }
+    }
+
+    for range in relocation_ranges(view, offset, size as u64).into_iter() {
+        for address in range.start.max(offset)..range.end.min(offset + size as u64) {
+            reasons[(address - offset) as usize] = Some(WildcardReason::Relocation);
+        }
+    }
+
+    for index in (size - wildcard)..size {
+        reasons[index] = reasons[index].or(Some(WildcardReason::Pointer));
+    }
+
+    if policy != WildcardPolicy::Pointers {
+        if let Some(width) = branch_displacement(&bytes) {
+            if width < size {
+                for index in (size - width)..size {
+                    reasons[index] = reasons[index].or(Some(WildcardReason::Branch));
+                }
+            }
+        }
+    }
+
+    let (displacements, registers) = stack_operands(base, &bytes, offset);
+    let mut fragile = registers;
+
+    for displacement in displacements.into_iter() {
+        //  Never look at the opcode byte itself
+        for (index, width) in find_encoded(&bytes, displacement).into_iter().filter(|(index, _)| *index > 0) {
+            fragile += width;
+
+            if policy == WildcardPolicy::Aggressive {
+                for position in index..(index + width) {
+                    reasons[position] = reasons[position].or(Some(WildcardReason::Stack));
+                }
+            }
+        }
+    }
+
+    let sig_bytes = bytes.iter().zip(reasons.iter())
+        .map(|(byte, reason)| match reason {
+            Some(_) => SigByte::Wildcard,
+            None => SigByte::Match(*byte),
+        })
+        .collect();
+
+    return Ok(InstructionBytes { address: offset, bytes: sig_bytes, reasons, fragile });
+}
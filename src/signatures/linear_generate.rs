@@ -6,23 +6,65 @@ use std::io::{Read, Seek, SeekFrom};
 use binaryninja::binaryreader::BinaryReader;
 use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
 use binaryninja::function::Function;
-use binaryninja::interaction::show_message_box;
 use binaryninja::rc;
 use log::{error, info, warn};
 use crate::SigByte;
-use crate::signatures::generate::consume_instruction;
-use crate::signatures::scan::sig_matches;
-use crate::utils::function::{find_func_end, read_view};
+use crate::settings::generate_options;
+use crate::signatures::index::cached_index;
+use crate::signatures::instruction::analyse_instruction;
+use crate::signatures::minimise::{count_concrete, minimise_signature, unique_prefix};
+use crate::signatures::options::{GenerateOptions, Hardening, WildcardPolicy};
+use crate::signatures::scan::{find_in_buffer, hamming_margin, sig_matches};
+use crate::utils::function::{buffer_info, find_func_end, in_scan_scope, read_view};
 
 pub fn linear_generate_signature(view: &BinaryView, sig_address: u64, func: rc::Ref<Function>) -> Result<Vec<SigByte>, String> {
 
     let buf = read_view(view);
 
+    return linear_generate_signature_with(view, &buf, sig_address, func, &generate_options(view));
+}
+
+pub fn linear_generate_signature_with(view: &BinaryView, buf: &Vec<u8>, sig_address: u64, func: rc::Ref<Function>, options: &GenerateOptions) -> Result<Vec<SigByte>, String> {
+
+    let sig = linear_generate_unique(view, buf, sig_address, func.to_owned(), options.policy)?;
+
+    return Ok(finish_signature(view, buf, sig_address, func, sig, options));
+}
+
+//  Harden a signature that is already unique, then minimise it if the options ask for it
+pub fn finish_signature(view: &BinaryView, buf: &Vec<u8>, sig_address: u64, func: rc::Ref<Function>, mut sig: Vec<SigByte>, options: &GenerateOptions) -> Vec<SigByte> {
+    let core_len = sig.len();
+
+    harden_signature(view, buf, sig_address, func, &mut sig, options.hardening, options.policy);
+
+    if options.minimise {
+        let target = sig_address - view.start();
+        let (minimised, report) = minimise_signature(&sig, core_len, buf, target, options.hardening);
+        info!("[SMTools] Minimise: wildcarded {0} bytes, trimmed {1} bytes ({2} -> {3} bytes, {4} -> {5} concrete)",
+            report.wildcarded, report.trimmed, report.original_length, minimised.len(),
+            report.original_concrete, count_concrete(&minimised));
+
+        let margin = measure_margin(&minimised, buf, target);
+        info!("[SMTools] Margin after minimising: distance {0}, {1} extra concrete bytes", margin.describe_distance(), margin.extra_concrete);
+        sig = minimised;
+    }
+
+    return sig;
+}
+
+//  Shortest run of whole instructions starting at `sig_address` that is unique in `buf`
+pub fn linear_generate_unique(view: &BinaryView, buf: &Vec<u8>, sig_address: u64, func: rc::Ref<Function>, policy: WildcardPolicy) -> Result<Vec<SigByte>, String> {
+
+    if cached_index(buf).is_some() {
+        return indexed_generate_unique(view, buf, sig_address, func, policy);
+    }
+
     let mut sig: Vec<SigByte> = Vec::new();
     let mut func_end = find_func_end(&func);
+    let info = buffer_info(buf);
 
 
-    for address in 0..(buf.len() - (sig.len()) - 1) as u64 {
+    for address in 0..buf.len() as u64 {
 
         let real_address = address + view.start();
 
@@ -31,44 +73,119 @@ pub fn linear_generate_signature(view: &BinaryView, sig_address: u64, func: rc::
             continue;
         }
 
-        while sig_matches(&sig, buf.as_ref(), address) {
+        while sig_matches(&sig, buf, address) && in_scan_scope(&info, address, sig.len()) {
 
             if func_end <= (sig_address + (sig.len() as u64)) {
                 warn!("[SMTools] HIT FUNC LIMIT");
-                show_message_box("SMTools", "There was not enough unique bytes left in the function to create a signature.", binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
                 return Err("Not enough unique bytes in the remainder of the subroutine".to_string());
             }
 
             let pointer = sig_address + (sig.len() as u64);
-            let mut instruction = consume_instruction(func.as_ref(), view, pointer);
+            let mut instruction = analyse_instruction(func.as_ref(), view, pointer, policy);
             if let Err(msg) = &instruction
             {
                 return Err(format!("Error scanning: {0}", msg));
             }
-            sig.append(&mut instruction.expect("Impossible error"));
+            sig.append(&mut instruction.expect("Impossible error").bytes);
+
+        }
+    }
+
+    return Ok(sig);
+}
+
+//  Same as linear_generate_unique, but asks the index after every instruction instead of scanning
+fn indexed_generate_unique(view: &BinaryView, buf: &Vec<u8>, sig_address: u64, func: rc::Ref<Function>, policy: WildcardPolicy) -> Result<Vec<SigByte>, String> {
+
+    let mut sig: Vec<SigByte> = Vec::new();
+    let func_end = find_func_end(&func);
 
+    while sig.len() == 0 || find_in_buffer(&sig, buf, 0, 2).len() > 1 {
+
+        if func_end <= (sig_address + (sig.len() as u64)) {
+            warn!("[SMTools] HIT FUNC LIMIT");
+            return Err("Not enough unique bytes in the remainder of the subroutine".to_string());
         }
+
+        let pointer = sig_address + (sig.len() as u64);
+        let mut instruction = analyse_instruction(func.as_ref(), view, pointer, policy);
+        if let Err(msg) = &instruction
+        {
+            return Err(format!("Error scanning: {0}", msg));
+        }
+        sig.append(&mut instruction.expect("Impossible error").bytes);
     }
-    //  Now, append a few instructions if we still have room
-    //  Just to make the signature a little bit more resilient to collisions
-    for addition in 0..3
+
+    return Ok(sig);
+}
+
+//  Distances at or past this are reported as ">= MARGIN_CAP"
+pub const MARGIN_CAP: usize = 32;
+
+pub struct Margin {
+    //  Fewest differing concrete bytes at any other position
+    pub distance: usize,
+    //  Concrete bytes after the point of uniqueness
+    pub extra_concrete: usize,
+}
+
+impl Margin
+{
+    pub fn describe_distance(&self) -> String {
+        if self.distance >= MARGIN_CAP {
+            return format!(">= {0}", MARGIN_CAP);
+        }
+        return self.distance.to_string();
+    }
+}
+
+pub fn measure_margin(sig: &Vec<SigByte>, buf: &Vec<u8>, target: u64) -> Margin {
+    let needed = unique_prefix(sig, buf, target).unwrap_or(sig.len());
+
+    return Margin {
+        distance: hamming_margin(sig, buf, target, MARGIN_CAP),
+        extra_concrete: count_concrete(&sig[needed.min(sig.len())..]),
+    };
+}
+
+fn margin_reached(hardening: Hardening, sig: &Vec<SigByte>, core_len: usize, appended: usize, buf: &Vec<u8>, target: u64) -> bool {
+    return match hardening {
+        Hardening::Instructions(count) => appended >= count,
+        Hardening::Distance(distance) => hamming_margin(sig, buf, target, distance) >= distance,
+        Hardening::ExtraBytes(count) => count_concrete(&sig[core_len..]) >= count,
+    };
+}
+
+//  Now, append instructions until the margin is reached, if we still have room
+//  Just to make the signature a little bit more resilient to collisions
+pub fn harden_signature(view: &BinaryView, buf: &Vec<u8>, sig_address: u64, func: rc::Ref<Function>, sig: &mut Vec<SigByte>, hardening: Hardening, policy: WildcardPolicy) -> Margin {
+    let func_end = find_func_end(&func);
+    let target = sig_address - view.start();
+    let core_len = sig.len();
+    let mut appended = 0;
+
+    while !margin_reached(hardening, sig, core_len, appended, buf, target)
     {
         if func_end <= (sig_address + (sig.len() as u64)) {
-            warn!("[SMTools] Warning: Hit func end while hardening signature ({0}/4).", addition);
+            warn!("[SMTools] Warning: Hit func end while hardening signature ({0}).", hardening.describe());
             warn!("[SMTools] Signature is still unique, but may be more likely to collide during updates.");
             break;
         }
 
         let pointer = sig_address + (sig.len() as u64);
-        let mut instruction = consume_instruction(func.as_ref(), view, pointer);
+        let mut instruction = analyse_instruction(func.as_ref(), view, pointer, policy);
         if let Err(msg) = &instruction
         {
-            warn!("[SMTools] Warning: Error hardening signature ({0}/4): {1}", addition, msg);
+            warn!("[SMTools] Warning: Error hardening signature ({0}): {1}", hardening.describe(), msg);
             warn!("[SMTools] Signature is still unique, but may be more likely to collide during updates.");
             break;
         }
-        sig.append(&mut instruction.expect("Impossible error"));
+        sig.append(&mut instruction.expect("Impossible error").bytes);
+        appended += 1;
     }
 
-    return Ok(sig);
-}
\ No newline at end of file
+    let margin = measure_margin(sig, buf, target);
+    info!("[SMTools] Hardening margin: distance {0}, {1} extra concrete bytes (target {2})", margin.describe_distance(), margin.extra_concrete, hardening.describe());
+
+    return margin;
+}
@@ -0,0 +1,157 @@
+
+//  Member generate:
+//  Sign an instruction that accesses a struct member, so plugins can
+//  read the member's offset out of the displacement at load time.
+
+use std::time::Instant;
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::function::Function;
+use binaryninja::interaction::show_message_box;
+use log::{info, warn};
+use crate::gamedata::writer::{library_name, platform_key, KeyValuesWriter};
+use crate::report::Report;
+use crate::settings::{default_dialect, generate_options, wildcard_policy};
+use crate::signatures::dialect::log_signature;
+use crate::signatures::instruction::find_encoded;
+use crate::signatures::linear_generate::linear_generate_signature_with;
+use crate::signatures::options::GenerateOptions;
+use crate::signatures::scan::find_signature;
+use crate::signatures::sigbyte::SigByte;
+use crate::signatures::store::remember_signature;
+use crate::signatures::signature::{Capture, Signature};
+use crate::utils::function::{find_address_base, instruction_constants, read_view};
+use crate::utils::interaction::get_choice_input;
+
+//  Every place a non-pointer constant of the instruction is encoded in its bytes
+fn find_displacements(base: &Function, offset: u64, bytes: &Vec<u8>) -> Vec<Capture> {
+    let mut found: Vec<Capture> = Vec::new();
+
+    for constant in instruction_constants(base, offset).into_iter() {
+        if constant.pointer {
+            continue;
+        }
+
+        for (index, width) in find_encoded(bytes, constant.value).into_iter() {
+            let covered = found.iter().any(|c| index >= c.offset && index + width <= c.offset + c.width);
+            if !covered {
+                found.push(Capture { offset: index, width, value: constant.value });
+            }
+        }
+    }
+
+    return found;
+}
+
+pub fn member_generate_signature(view: &BinaryView, offset: u64, capture_mode: bool) -> Result<Signature, String> {
+    let func = find_address_base(view, offset).map_err(|reason| reason.to_string())?;
+
+    let arch = func.arch();
+    let size = match view.instruction_len(arch.as_ref(), offset) {
+        Some(size) if size != 0 => size,
+        _ => return Err(format!("Failure getting instruction length at {0}.", offset)),
+    };
+    let bytes = view.read_vec(offset, size);
+
+    let candidates = find_displacements(func.as_ref(), offset, &bytes);
+    let capture = match candidates.len() {
+        0 => return Err("The instruction does not encode a displacement".to_string()),
+        1 => candidates[0],
+        _ => {
+            let labels: Vec<String> = candidates.iter()
+                .map(|c| format!("{0:#x} ({1} bytes at +{2})", c.value, c.width, c.offset))
+                .collect();
+            let choices: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+
+            match get_choice_input("Displacement", "SMTools", &choices) {
+                Some(index) if index < candidates.len() => candidates[index],
+                _ => return Err("No displacement selected".to_string()),
+            }
+        }
+    };
+
+    //  Minimising could trim or wildcard the displacement we are after
+    let options = GenerateOptions { minimise: false, ..generate_options(view) };
+    let mut sig = linear_generate_signature_with(view, &read_view(view), offset, func.to_owned(), &options)?;
+
+    //  The signature starts at the instruction, so the capture offset carries over
+    if capture_mode {
+        let mut captured = sig.to_owned();
+        for index in capture.offset..(capture.offset + capture.width) {
+            if index < captured.len() {
+                captured[index] = SigByte::Wildcard;
+            }
+        }
+
+        if find_signature(&captured, view, 2).len() == 1 {
+            sig = captured;
+        }
+        else {
+            warn!("[SMTools] Signature is not unique with the displacement wildcarded, keeping it concrete");
+        }
+    }
+
+    let mut signature = Signature::new(sig);
+    signature.capture = Some(capture);
+
+    return Ok(signature);
+}
+
+pub fn generate_and_print_member_signature(view: &BinaryView, offset: u64) {
+    if !view.offset_valid(offset) {
+        warn!("[SMTools] Invalid Address");
+        return;
+    }
+
+    let name = match find_address_base(view, offset) {
+        Ok(func) => format!("{0}+{1:#x}", func.symbol().full_name(), offset - func.start()),
+        Err(_) => format!("sub_{0:x}", offset),
+    };
+
+    let capture_mode = match get_choice_input("Displacement bytes", "SMTools", &["Concrete", "Capture (wildcard)"]) {
+        Some(index) => index == 1,
+        None => return,
+    };
+
+    let now = Instant::now();
+    let result = member_generate_signature(view, offset, capture_mode);
+    info!("[SMTools] Member scan completed in {0}ms", now.elapsed().as_millis());
+
+    match result {
+        Ok(signature) => {
+            info!("[SMTools] Member offset signature for '{0}'", name);
+            log_signature(view, &signature.bytes);
+            remember_signature(view, &name, offset, &signature, default_dialect(view), wildcard_policy(view));
+
+            let mut report = Report::new("SMTools: Member offset signature");
+            if let Some(capture) = signature.capture {
+                report.note(&format!("Displacement {0:#x}: {1} bytes at +{2:#x}", capture.value, capture.width, capture.offset));
+            }
+            report.signature(&name, offset, &signature)
+                .timing("Member scan", now.elapsed())
+                .publish(view);
+
+            if let Some(capture) = signature.capture {
+                info!("[SMTools] Current value: {0:#x} ({0})", capture.value);
+                info!("[SMTools] Capture: {0} bytes at +{1:#x} ({1})", capture.width, capture.offset);
+
+                let mut writer = KeyValuesWriter::new();
+                writer.open("Signatures")
+                    .open(&name)
+                    .value("library", &library_name(view))
+                    .value(&platform_key(view), &signature.to_str_sm())
+                    .close()
+                    .close();
+                writer.open("Offsets")
+                    .open(&name)
+                    .value(&platform_key(view), &capture.value.to_string())
+                    .close()
+                    .close();
+                info!("[SMTools] Gamedata:\n{0}", writer.finish());
+            }
+        }
+        Err(reason) => {
+            warn!("[SMTools] Failed to get member offset signature for '{0}': '{1}'", name, reason);
+            show_message_box("SMTools", &format!("Failed to get a member offset signature: {0}", reason), binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+
+//  Minimise:
+//  Once a signature is unique, wildcard every concrete byte that uniqueness does not
+//  depend on and drop the tail bytes that are not needed for the hardening margin.
+
+use crate::signatures::index::cached_index;
+use crate::signatures::options::Hardening;
+use crate::signatures::scan::{find_in_buffer, hamming_margin, near_matches};
+use crate::signatures::sigbyte::SigByte;
+use crate::utils::function::{buffer_info, in_scan_scope};
+
+pub struct MinimiseReport {
+    pub original_length: usize,
+    pub original_concrete: usize,
+    //  Concrete bytes turned into wildcards
+    pub wildcarded: usize,
+    //  Bytes removed from the tail
+    pub trimmed: usize,
+}
+
+//  What the minimised signature must still satisfy
+enum MarginTarget {
+    //  This many concrete bytes after the point of uniqueness
+    Concrete(usize),
+    //  Every other position differs in at least this many concrete bytes
+    Distance(usize),
+}
+
+pub fn count_concrete(sig: &[SigByte]) -> usize {
+    return sig.iter().filter(|b| matches!(b, SigByte::Match(_))).count();
+}
+
+//  Shortest prefix of `sig` which matches nowhere but `target`, if any
+pub fn unique_prefix(sig: &Vec<SigByte>, buf: &Vec<u8>, target: u64) -> Option<usize> {
+    let mut needed = 0;
+
+    if buf.len() < sig.len() {
+        return Some(0);
+    }
+
+    if cached_index(buf).is_some() {
+        return indexed_unique_prefix(buf, sig, (buf.len() - sig.len()) as u64, target);
+    }
+
+    let info = buffer_info(buf);
+    for address in 0..=(buf.len() - sig.len()) {
+        if address as u64 == target || !in_scan_scope(&info, address as u64, sig.len()) {
+            continue;
+        }
+
+        let first_mismatch = sig.iter().enumerate().position(|(index, byte)| match byte {
+            SigByte::Wildcard => false,
+            SigByte::Match(value) => *value != buf[address + index],
+        });
+
+        match first_mismatch {
+            Some(index) => needed = needed.max(index + 1),
+            None => return None,
+        }
+    }
+
+    return Some(needed);
+}
+
+//  Same as unique_prefix: matches of a prefix only shrink as it grows, so binary search
+//  for the shortest one that matches nowhere else. Like the scan, only positions up to
+//  `last` (where the whole signature fits) count.
+fn indexed_unique_prefix(buf: &Vec<u8>, sig: &Vec<SigByte>, last: u64, target: u64) -> Option<usize> {
+    let info = buffer_info(buf);
+    let elsewhere = |len: usize| -> bool {
+        let prefix = sig[..len].to_vec();
+        return find_in_buffer(&prefix, buf, 0, 2).into_iter()
+            .any(|address| address != target && address <= last && in_scan_scope(&info, address, sig.len()));
+    };
+
+    if elsewhere(sig.len()) {
+        return None;
+    }
+
+    let mut low = 0;
+    let mut high = sig.len();
+    while low < high {
+        let middle = (low + high) / 2;
+        if elsewhere(middle) {
+            low = middle + 1;
+        }
+        else {
+            high = middle;
+        }
+    }
+
+    return Some(high);
+}
+
+//  Greedily wildcard bytes of sig[..end] while every other position keeps at least
+//  `required` mismatches. Only positions within `bound` mismatches are tracked,
+//  so the result must be verified.
+fn wildcard_pass(sig: &mut Vec<SigByte>, end: usize, buf: &Vec<u8>, target: u64, bound: usize, required: usize) -> usize {
+    let window = sig[..end].to_vec();
+    let mut near: Vec<Vec<usize>> = near_matches(&window, buf, bound).into_iter()
+        .filter(|(address, _)| *address != target)
+        .map(|(_, mismatches)| mismatches)
+        .collect();
+
+    let mut wildcarded = 0;
+
+    //  Work from the back so the leading bytes stay concrete, which scanners prefer
+    for index in (1..end).rev() {
+        if !matches!(sig[index], SigByte::Match(_)) {
+            continue;
+        }
+
+        //  This byte is needed to keep some other position far enough away
+        if near.iter().any(|mismatches| mismatches.len() <= required && mismatches.contains(&index)) {
+            continue;
+        }
+
+        sig[index] = SigByte::Wildcard;
+        for mismatches in near.iter_mut() {
+            mismatches.retain(|i| *i != index);
+        }
+        wildcarded += 1;
+    }
+
+    return wildcarded;
+}
+
+//  `sig` must be unique at buffer offset `target`, and sig[..core_len] must already be unique
+//  on its own. Whatever margin the hardening reached is kept.
+pub fn minimise_signature(sig: &Vec<SigByte>, core_len: usize, buf: &Vec<u8>, target: u64, hardening: Hardening) -> (Vec<SigByte>, MinimiseReport) {
+    let margin = match hardening {
+        Hardening::Distance(distance) => MarginTarget::Distance(hamming_margin(sig, buf, target, distance).max(1)),
+        _ => MarginTarget::Concrete(count_concrete(&sig[core_len..])),
+    };
+
+    let mut report = MinimiseReport {
+        original_length: sig.len(),
+        original_concrete: count_concrete(sig),
+        wildcarded: 0,
+        trimmed: 0,
+    };
+
+    let (end, required) = match margin {
+        MarginTarget::Concrete(_) => (core_len, 1),
+        MarginTarget::Distance(distance) => (sig.len(), distance),
+    };
+
+    let mut minimised = sig.to_owned();
+    let mut bound = required + 1;
+
+    loop {
+        let mut attempt = sig.to_owned();
+        let wildcarded = wildcard_pass(&mut attempt, end, buf, target, bound, required);
+
+        let valid = match margin {
+            MarginTarget::Concrete(_) => unique_prefix(&attempt[..end].to_vec(), buf, target).is_some(),
+            MarginTarget::Distance(distance) => hamming_margin(&attempt, buf, target, distance) >= distance,
+        };
+
+        if valid {
+            minimised = attempt;
+            report.wildcarded = wildcarded;
+            break;
+        }
+
+        //  A position outside the bound got too close, so track more of them
+        if bound >= end {
+            break;
+        }
+        bound = (bound * 2).min(end);
+    }
+
+    let keep = match margin {
+        //  The shortest unique prefix, plus as many concrete bytes as the margin had
+        MarginTarget::Concrete(count) => match unique_prefix(&minimised, buf, target) {
+            Some(needed) => {
+                let mut keep = needed;
+                let mut remaining = count;
+
+                while remaining > 0 && keep < minimised.len() {
+                    if matches!(minimised[keep], SigByte::Match(_)) {
+                        remaining -= 1;
+                    }
+                    keep += 1;
+                }
+                keep
+            }
+            None => minimised.len(),
+        },
+        //  The distance only grows with length, so search for the shortest prefix keeping it
+        MarginTarget::Distance(distance) => {
+            let mut low = 1;
+            let mut high = minimised.len();
+
+            while low < high {
+                let middle = (low + high) / 2;
+                if hamming_margin(&minimised[..middle].to_vec(), buf, target, distance) >= distance {
+                    high = middle;
+                }
+                else {
+                    low = middle + 1;
+                }
+            }
+            high
+        }
+    };
+
+    report.trimmed = minimised.len() - keep;
+    minimised.truncate(keep);
+
+    return (minimised, report);
+}
@@ -1,4 +1,24 @@
 pub mod sigbyte;
+pub mod signature;
 pub mod scan;
 pub mod linear_generate;
-pub mod generate;
\ No newline at end of file
+pub mod generate;
+pub mod xref_generate;
+pub mod data_generate;
+pub mod member_generate;
+pub mod options;
+pub mod instruction;
+pub mod candidates;
+pub mod minimise;
+pub mod collision;
+pub mod fuzzy;
+pub mod repair;
+pub mod cross_version;
+pub mod index;
+pub mod batch;
+pub mod survey;
+pub mod range_generate;
+pub mod dialect;
+pub mod store;
+pub mod generate_form;
+pub mod explain;
\ No newline at end of file
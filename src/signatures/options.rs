@@ -0,0 +1,86 @@
+//  Which bytes of an instruction become wildcards
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WildcardPolicy {
+    //  Only pointer constants (relocated or rebased every build)
+    Pointers,
+    //  Pointers and relative branch displacements
+    Branches,
+    //  Pointers, branches and stack offsets
+    Aggressive,
+}
+
+impl WildcardPolicy
+{
+    pub const ALL: [WildcardPolicy; 3] = [WildcardPolicy::Pointers, WildcardPolicy::Branches, WildcardPolicy::Aggressive];
+
+    pub fn name(&self) -> &'static str {
+        match &self {
+            WildcardPolicy::Pointers => "pointers",
+            WildcardPolicy::Branches => "branches",
+            WildcardPolicy::Aggressive => "aggressive",
+        }
+    }
+}
+
+//  How far past uniqueness a signature is extended, so it survives small changes elsewhere
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Hardening {
+    //  Append a fixed number of instructions
+    Instructions(usize),
+    //  Append instructions until every other position differs in at least N concrete bytes
+    Distance(usize),
+    //  Append instructions until at least N concrete bytes follow the point of uniqueness
+    ExtraBytes(usize),
+}
+
+impl Hardening
+{
+    pub const NAMES: [&'static str; 3] = ["instructions", "distance", "extraBytes"];
+
+    //  Key used in settings
+    pub fn name(&self) -> &'static str {
+        match &self {
+            Hardening::Instructions(_) => "instructions",
+            Hardening::Distance(_) => "distance",
+            Hardening::ExtraBytes(_) => "extraBytes",
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        match &self {
+            Hardening::Instructions(count) | Hardening::Distance(count) | Hardening::ExtraBytes(count) => *count,
+        }
+    }
+
+    //  Unknown names fall back to distance, the default
+    pub fn from_name(name: &str, count: usize) -> Hardening {
+        return match name {
+            "instructions" => Hardening::Instructions(count),
+            "extraBytes" => Hardening::ExtraBytes(count),
+            _ => Hardening::Distance(count),
+        };
+    }
+
+    pub fn describe(&self) -> String {
+        match &self {
+            Hardening::Instructions(count) => format!("{0} instructions", count),
+            Hardening::Distance(distance) => format!("distance {0}", distance),
+            Hardening::ExtraBytes(count) => format!("{0} extra bytes", count),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct GenerateOptions {
+    pub policy: WildcardPolicy,
+    //  Margin added once the signature is unique
+    pub hardening: Hardening,
+    //  Wildcard and trim whatever uniqueness does not depend on
+    pub minimise: bool,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> GenerateOptions {
+        return GenerateOptions { policy: WildcardPolicy::Pointers, hardening: Hardening::Distance(4), minimise: true };
+    }
+}
@@ -0,0 +1,152 @@
+
+//  Range generate:
+//  Sign exactly the selected bytes, with the wildcard policy applied per instruction,
+//  and let the user flip individual bytes before the result is exported.
+
+use std::ops::Range;
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use log::{info, warn};
+use crate::gamedata::writer::{write_signature_entry, KeyValuesWriter};
+use crate::report::{address_link, Report};
+use crate::settings::{default_dialect, wildcard_policy};
+use crate::signatures::dialect::log_signature;
+use crate::signatures::collision::{collision_report, print_collision_report};
+use crate::signatures::instruction::analyse_instruction;
+use crate::signatures::options::WildcardPolicy;
+use crate::signatures::scan::find_signature;
+use crate::signatures::sigbyte::{parse_signature, SigByte};
+use crate::signatures::store::remember_signature;
+use crate::signatures::signature::Signature;
+use crate::utils::function::find_address_base;
+use crate::utils::interaction::get_edited_text_input;
+
+//  Stop scanning for matches past this, the report only needs to show there are several
+const MAX_MATCHES: usize = 10;
+
+pub fn range_generate_signature(view: &BinaryView, range: &Range<u64>, policy: WildcardPolicy) -> Result<Vec<SigByte>, String> {
+    let raw = view.read_vec(range.start, (range.end - range.start) as usize);
+    if raw.len() as u64 != range.end - range.start {
+        return Err(format!("Failed to read {0:#08X}-{1:#08X}", range.start, range.end));
+    }
+
+    //  Outside of code there is nothing to wildcard, take the bytes as they are
+    let func = match find_address_base(view, range.start) {
+        Ok(func) => func,
+        Err(_) => return Ok(raw.into_iter().map(SigByte::Match).collect()),
+    };
+
+    let mut sig: Vec<SigByte> = Vec::new();
+    while (sig.len() as u64) < range.end - range.start {
+        let pointer = range.start + sig.len() as u64;
+        match analyse_instruction(func.as_ref(), view, pointer, policy) {
+            Ok(mut instruction) if instruction.bytes.len() > 0 => sig.append(&mut instruction.bytes),
+            //  Not an instruction (eg. inline data), keep the remaining bytes concrete
+            _ => sig.extend(raw[sig.len()..].iter().map(|b| SigByte::Match(*b))),
+        }
+    }
+
+    //  The last instruction may run past the selection
+    sig.truncate(raw.len());
+
+    return Ok(sig);
+}
+
+//  Check an edited signature only flips bytes between wildcard and the byte actually there
+fn apply_overrides(edited: &str, raw: &Vec<u8>) -> Result<Vec<SigByte>, String> {
+    let parsed = parse_signature(edited.to_owned())?;
+
+    if parsed.len() != raw.len() {
+        return Err(format!("Expected {0} bytes, got {1}", raw.len(), parsed.len()));
+    }
+
+    for (index, (byte, actual)) in parsed.iter().zip(raw.iter()).enumerate() {
+        if let SigByte::Match(value) = byte {
+            if value != actual {
+                return Err(format!("Byte {0} is {1:02X} in the view, not {2:02X}", index, actual, value));
+            }
+        }
+    }
+
+    return Ok(parsed);
+}
+
+fn report_uniqueness(view: &BinaryView, sig: &Vec<SigByte>, target: u64) -> bool {
+    let matches = find_signature(sig, view, MAX_MATCHES + 1);
+
+    match matches.len() {
+        0 => warn!("[SMTools] The signature does not match anywhere, not even the selection"),
+        1 if matches[0] == target => info!("[SMTools] The signature is unique"),
+        _ => {
+            let collisions = collision_report(view, sig, target, MAX_MATCHES);
+            print_collision_report(target, sig.len(), &collisions);
+        }
+    }
+
+    return matches.len() == 1 && matches[0] == target;
+}
+
+pub fn generate_and_print_range_signature(view: &BinaryView, range: Range<u64>) {
+    if range.end <= range.start || !view.offset_valid(range.start) {
+        warn!("[SMTools] Invalid Range");
+        return;
+    }
+
+    let mut sig = match range_generate_signature(view, &range, wildcard_policy(view)) {
+        Ok(sig) => sig,
+        Err(reason) => {
+            warn!("[SMTools] Failed to get range signature: '{0}'", reason);
+            return;
+        }
+    };
+    let raw = view.read_vec(range.start, sig.len());
+
+    info!("[SMTools] Range signature for {0:#08X}-{1:#08X} ({2} bytes)", range.start, range.end, sig.len());
+    let mut unique = report_uniqueness(view, &sig, range.start);
+
+    //  Re-check after every edit, until the user accepts the bytes unchanged
+    let label = format!("Bytes in view: {0}", raw.iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" "));
+    loop {
+        let current = sig.iter().map(|s| s.to_str_generic()).collect::<String>();
+        let prompt = if unique { "Signature (unique)" } else { "Signature (NOT unique)" };
+
+        let edited = match get_edited_text_input(&label, prompt, "SMTools", current.trim()) {
+            Some(edited) => edited,
+            None => return,
+        };
+
+        match apply_overrides(&edited, &raw) {
+            Ok(overridden) if overridden == sig => break,
+            Ok(overridden) => {
+                sig = overridden;
+                unique = report_uniqueness(view, &sig, range.start);
+            }
+            Err(reason) => warn!("[SMTools] Ignoring edit: {0}", reason),
+        }
+    }
+
+    let name = match find_address_base(view, range.start) {
+        Ok(func) => format!("{0}+{1:#x}", func.symbol().full_name(), range.start - func.start()),
+        Err(_) => format!("range_{0:x}", range.start),
+    };
+
+    if !unique {
+        warn!("[SMTools] Exporting a signature that is not unique");
+    }
+
+    let signature = Signature::new(sig);
+    log_signature(view, &signature.bytes);
+    remember_signature(view, &name, range.start, &signature, default_dialect(view), wildcard_policy(view));
+
+    let mut report = Report::new("SMTools: Range signature");
+    report.note(&format!("{0} bytes from {1}", signature.bytes.len(), address_link(range.start)));
+    if !unique {
+        report.note("The signature is NOT unique");
+    }
+    report.signature(&name, range.start, &signature).publish(view);
+
+    let mut writer = KeyValuesWriter::new();
+    writer.open("Signatures");
+    write_signature_entry(&mut writer, view, &name, &signature);
+    writer.close();
+    info!("[SMTools] Gamedata:\n{0}", writer.finish());
+}
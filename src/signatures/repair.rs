@@ -0,0 +1,254 @@
+
+//  Repair:
+//  Find where a broken signature moved to in this build and regenerate it there.
+//  Anything that can't be placed confidently is flagged, never guessed.
+
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use log::info;
+use crate::gamedata::file::Gamedata;
+use crate::settings::generate_options;
+use crate::gamedata::writer::{library_name, platform_key, KeyValuesWriter};
+use crate::signatures::collision::function_tail_signature;
+use crate::signatures::fuzzy::{fuzzy_scan, FuzzyDiff, FuzzyOptions};
+use crate::signatures::linear_generate::linear_generate_signature_with;
+use crate::signatures::options::{GenerateOptions, WildcardPolicy};
+use crate::signatures::scan::find_in_buffer;
+use crate::signatures::sigbyte::{parse_signature, SigByte};
+use crate::utils::function::find_address_base;
+
+//  Lowest fuzzy score accepted as the new location
+const CONFIDENT_SCORE: f64 = 75.0;
+//  How far ahead of the runner-up the best near-match must be
+const CONFIDENT_LEAD: f64 = 10.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RepairStatus {
+    //  Still exactly one match, nothing to do
+    Intact,
+    Repaired,
+    //  Could not be placed confidently
+    Flagged(String),
+    //  Not something we can repair, such as "@symbol" lookups
+    Skipped(String),
+}
+
+pub struct RepairResult {
+    pub name: String,
+    pub old_text: String,
+    pub old_matches: usize,
+    pub status: RepairStatus,
+    pub location: Option<u64>,
+    pub confidence: Option<f64>,
+    pub policy: Option<WildcardPolicy>,
+    pub diffs: Vec<FuzzyDiff>,
+    pub new: Option<Vec<SigByte>>,
+}
+
+impl RepairStatus
+{
+    pub fn describe(&self) -> String {
+        match &self {
+            RepairStatus::Intact => "intact".to_owned(),
+            RepairStatus::Repaired => "repaired".to_owned(),
+            RepairStatus::Flagged(reason) => format!("FLAGGED: {0}", reason),
+            RepairStatus::Skipped(reason) => format!("skipped: {0}", reason),
+        }
+    }
+}
+
+//  The policy whose wildcards line up best with the ones in the old signature
+fn infer_policy(view: &BinaryView, address: u64, old: &Vec<SigByte>) -> WildcardPolicy {
+    let func = match find_address_base(view, address) {
+        Ok(func) => func,
+        Err(_) => return WildcardPolicy::Pointers,
+    };
+
+    let mut best = (WildcardPolicy::Pointers, 0);
+
+    for policy in WildcardPolicy::ALL {
+        let tail = function_tail_signature(view, func.as_ref(), address, policy);
+        let agreement = tail.iter().zip(old.iter())
+            .filter(|(a, b)| matches!(a, SigByte::Wildcard) == matches!(b, SigByte::Wildcard))
+            .count();
+
+        if agreement > best.1 {
+            best = (policy, agreement);
+        }
+    }
+
+    return best.0;
+}
+
+pub fn repair_signature(view: &BinaryView, buf: &Vec<u8>, name: &str, text: &str) -> RepairResult {
+    let mut result = RepairResult {
+        name: name.to_owned(),
+        old_text: text.to_owned(),
+        old_matches: 0,
+        status: RepairStatus::Intact,
+        location: None,
+        confidence: None,
+        policy: None,
+        diffs: Vec::new(),
+        new: None,
+    };
+
+    if text.starts_with('@') {
+        result.status = RepairStatus::Skipped("symbol lookup".to_owned());
+        return result;
+    }
+
+    let old = match parse_signature(text.to_owned()) {
+        Ok(old) => old,
+        Err(msg) => {
+            result.status = RepairStatus::Skipped(format!("failed to parse: {0}", msg));
+            return result;
+        }
+    };
+
+    let matches = find_in_buffer(&old, buf, view.start(), 2);
+    result.old_matches = matches.len();
+
+    match matches.len() {
+        1 => {
+            result.location = Some(matches[0]);
+            return result;
+        }
+        0 => {}
+        _ => {
+            result.status = RepairStatus::Flagged("signature matches more than once".to_owned());
+            return result;
+        }
+    }
+
+    let concrete = old.iter().filter(|b| matches!(b, SigByte::Match(_))).count();
+    let options = FuzzyOptions { max_mismatches: (concrete / 5).max(2), max_indels: 2 };
+    let near = fuzzy_scan(&old, buf, &options, 2);
+
+    let best = match near.first() {
+        Some(best) => best,
+        None => {
+            result.status = RepairStatus::Flagged("no near-match".to_owned());
+            return result;
+        }
+    };
+
+    result.confidence = Some(best.score);
+    result.diffs = best.diffs.to_owned();
+    let address = best.offset + view.start();
+
+    if best.score < CONFIDENT_SCORE {
+        result.status = RepairStatus::Flagged(format!("best near-match at {0:#08X} only scores {1:.1}", address, best.score));
+        return result;
+    }
+
+    if let Some(runner_up) = near.get(1) {
+        if best.score - runner_up.score < CONFIDENT_LEAD {
+            result.status = RepairStatus::Flagged(format!("near-matches at {0:#08X} and {1:#08X} are too close to call",
+                address, runner_up.offset + view.start()));
+            return result;
+        }
+    }
+
+    result.location = Some(address);
+
+    let func = match find_address_base(view, address) {
+        Ok(func) => func,
+        Err(reason) => {
+            result.status = RepairStatus::Flagged(format!("new location is not in a function: {0}", reason));
+            return result;
+        }
+    };
+
+    let policy = infer_policy(view, address, &old);
+    result.policy = Some(policy);
+
+    let generate = GenerateOptions { policy, ..generate_options(view) };
+    match linear_generate_signature_with(view, buf, address, func, &generate) {
+        Ok(sig) => {
+            result.new = Some(sig);
+            result.status = RepairStatus::Repaired;
+        }
+        Err(reason) => {
+            result.status = RepairStatus::Flagged(format!("regenerating failed: {0}", reason));
+        }
+    }
+
+    return result;
+}
+
+pub fn repair_gamedata(view: &BinaryView, buf: &Vec<u8>, gamedata: &Gamedata) -> Vec<RepairResult> {
+    let platform = platform_key(view);
+    let library = library_name(view);
+    let mut results = Vec::new();
+
+    for entry in gamedata.signatures.iter() {
+        if let Some(entry_library) = &entry.library {
+            if !entry_library.eq_ignore_ascii_case(&library) {
+                info!("[SMTools] Skipping '{0}': library '{1}' is not '{2}'", entry.name, entry_library, library);
+                continue;
+            }
+        }
+
+        match entry.for_platform(&platform) {
+            Some(text) => results.push(repair_signature(view, buf, &entry.name, text)),
+            None => info!("[SMTools] Skipping '{0}': no {1} signature", entry.name, platform),
+        }
+    }
+
+    return results;
+}
+
+pub fn format_repair_report(view: &BinaryView, results: &Vec<RepairResult>) -> String {
+    let mut report = String::new();
+
+    report.push_str("# SMTools repair report\n\n");
+    report.push_str(&format!("Library: {0} ({1})\n\n", library_name(view), platform_key(view)));
+    report.push_str("| Entry | Status | Old matches | Location | Confidence |\n");
+    report.push_str("|---|---|---|---|---|\n");
+
+    for result in results.iter() {
+        report.push_str(&format!("| {0} | {1} | {2} | {3} | {4} |\n",
+            result.name,
+            result.status.describe(),
+            result.old_matches,
+            result.location.map(|l| format!("{0:#08X}", l)).unwrap_or("-".to_owned()),
+            result.confidence.map(|c| format!("{0:.1}", c)).unwrap_or("-".to_owned())));
+    }
+
+    for result in results.iter().filter(|r| r.status != RepairStatus::Intact) {
+        report.push_str(&format!("\n## {0}\n\n", result.name));
+        report.push_str(&format!("Status: {0}\n\n", result.status.describe()));
+        report.push_str(&format!("Before: `{0}`\n\n", result.old_text));
+
+        if let Some(new) = &result.new {
+            report.push_str(&format!("After: `{0}`\n\n", new.iter().map(|s| s.to_str_sm()).collect::<String>()));
+        }
+        if let Some(policy) = result.policy {
+            report.push_str(&format!("Wildcard policy: {0}\n\n", policy.name()));
+        }
+        for diff in result.diffs.iter() {
+            report.push_str(&format!("- {0}\n", diff.describe()));
+        }
+    }
+
+    let repaired: Vec<&RepairResult> = results.iter().filter(|r| r.status == RepairStatus::Repaired).collect();
+    if repaired.len() > 0 {
+        let mut writer = KeyValuesWriter::new();
+        writer.open("Signatures");
+        for result in repaired.into_iter() {
+            if let Some(new) = &result.new {
+                writer.open(&result.name)
+                    .value("library", &library_name(view))
+                    .value(&platform_key(view), &new.iter().map(|s| s.to_str_sm()).collect::<String>())
+                    .close();
+            }
+        }
+        writer.close();
+
+        report.push_str("\n## Updated gamedata\n\n```\n");
+        report.push_str(&writer.finish());
+        report.push_str("```\n");
+    }
+
+    return report;
+}
@@ -1,54 +1,142 @@
 use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
-use binaryninja::interaction::show_message_box;
 use log::warn;
-use crate::utils::function::read_view;
+use crate::utils::function::{buffer_info, find_address_base, in_scan_scope, read_view};
+use crate::utils::tags::{add_address_tag, clear_tags_of_type};
 
+use super::index::cached_index;
 use super::sigbyte::SigByte;
+use super::signature::ResolveStep;
 
-pub fn sig_matches(signature: &Vec<SigByte>, buffer: &Vec<u8>, offset: u64) -> bool {
-    if (offset >= (usize::MAX as u64)) {
-        return false;
+pub use smtools_core::scan::{scan_buffer, sig_matches};
+
+pub fn find_signature(signature: &Vec<SigByte>, view: &BinaryView, maxmatches: usize) -> Vec<u64> {
+    let buf = read_view(view);
+
+    return find_in_buffer(signature, &buf, view.start(), maxmatches);
+}
+
+//  Same as find_signature, for a buffer already read from a view starting at `start`
+pub fn find_in_buffer(signature: &Vec<SigByte>, buf: &Vec<u8>, start: u64, maxmatches: usize) -> Vec<u64> {
+    if buf.len() < signature.len() {
+        return Vec::new();
     }
 
-    if (buffer.len() - (offset as usize) - 1) <= (signature.len()) {
-        return false;
+    //  Matches in blanked regions are dropped afterwards, so they can't use up the limit
+    let info = buffer_info(buf);
+    let limit = match info.as_ref().map(|info| info.scoped()) {
+        Some(true) => usize::MAX,
+        _ => maxmatches,
+    };
+
+    let found = match cached_index(buf) {
+        Some(index) => index.find(signature, limit),
+        None => scan_buffer(signature, buf, 0, limit),
+    };
+
+    return found.into_iter()
+        .filter(|offset| in_scan_scope(&info, *offset, signature.len()))
+        .take(maxmatches)
+        .map(|offset| offset + start)
+        .collect();
+}
+
+//  Same as smtools_core::scan::near_matches, through the index when there is one
+pub fn near_matches(signature: &Vec<SigByte>, buf: &Vec<u8>, max_mismatches: usize) -> Vec<(u64, Vec<usize>)> {
+    let found = match cached_index(buf) {
+        Some(index) => index.near(signature, max_mismatches),
+        None => smtools_core::scan::near_matches(signature, buf, max_mismatches),
+    };
+
+    let info = buffer_info(buf);
+    return found.into_iter()
+        .filter(|(offset, _)| in_scan_scope(&info, *offset, signature.len()))
+        .collect();
+}
+
+//  Same as smtools_core::scan::hamming_margin, through the index when there is one
+pub fn hamming_margin(signature: &Vec<SigByte>, buf: &Vec<u8>, target: u64, cap: usize) -> usize {
+    if cap == 0 {
+        return 0;
     }
 
-    for index in 0..signature.len() {
-        let mode = signature[index];
-        let byte = buffer[index + (offset as usize)];
+    return near_matches(signature, buf, cap - 1).into_iter()
+        .filter(|(address, _)| *address != target)
+        .map(|(_, mismatches)| mismatches.len())
+        .fold(cap, usize::min);
+}
+
+pub const MATCH_TAG: &str = "SMTools Match";
+const MATCH_ICON: &str = "🎯";
 
-        let good = match mode {
-            SigByte::Wildcard => true,
-            SigByte::Match(value) => value == byte,
-        };
+//  Tag every match with the signature text, so they can be browsed from the tag list
+pub fn tag_matches(view: &BinaryView, text: &str, matches: &Vec<u64>) -> usize {
+    let mut tagged = 0;
 
-        if !good {
-            return false;
+    for address in matches.iter() {
+        let func = find_address_base(view, *address).ok();
+        match add_address_tag(view, func.as_ref().map(|f| f.as_ref()), *address, MATCH_TAG, MATCH_ICON, text) {
+            Ok(()) => tagged += 1,
+            Err(msg) => warn!("[SMTools] Failed to tag {0:#08X}: {1}", address, msg),
         }
     }
 
-    return true;
+    return tagged;
 }
 
-pub fn find_signature(signature: &Vec<SigByte>, view: &BinaryView, maxmatches: usize) -> Vec<u64> {
-    let buf = read_view(view);
+pub fn clear_match_tags(view: &BinaryView) -> usize {
+    return clear_tags_of_type(view, MATCH_TAG);
+}
+
+fn read_pointer(view: &BinaryView, address: u64) -> Result<u64, String> {
+    let size = view.address_size();
+    let bytes = view.read_vec(address, size);
 
-    let mut matches = Vec::new();
+    if bytes.len() != size {
+        return Err(format!("Failed to read pointer at {0:#08X}", address));
+    }
+
+    let mut value: u64 = 0;
+    for (index, byte) in bytes.iter().enumerate() {
+        value |= (*byte as u64) << (index * 8);
+    }
 
-    for address in 0..(buf.len() - (signature.len()) - 1) as u64 {
-        let real_address = address + view.start();
-        if (sig_matches(signature, buf.as_ref(), real_address)) {
-            show_message_box("Match!", address.to_string(), Info)
-            matches.push(real_address);
+    return Ok(value);
+}
 
-            if (matches.len() >= maxmatches) {
-                //  Cut search short early
-                return matches;
+//  Walk a resolve chain from a match, returning the cursor after every step.
+//  The last entry is the resolved target.
+pub fn resolve_match(view: &BinaryView, address: u64, steps: &Vec<ResolveStep>) -> Result<Vec<u64>, String> {
+    let mut hops = Vec::new();
+    let mut cursor = address;
+
+    for step in steps.iter() {
+        cursor = match step {
+            ResolveStep::Offset(delta) => cursor.checked_add_signed(*delta)
+                .ok_or(format!("Offset {0} overflows at {1:#08X}", step.to_str(), cursor))?,
+            ResolveStep::Rel32 => {
+                let bytes = view.read_vec(cursor, 4);
+                if bytes.len() != 4 {
+                    return Err(format!("Failed to read rel32 at {0:#08X}", cursor));
+                }
+                let rel = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                cursor.checked_add(4).and_then(|end| end.checked_add_signed(rel as i64))
+                    .ok_or(format!("rel32 at {0:#08X} overflows", cursor))?
+            }
+            ResolveStep::Deref(count) => {
+                let mut value = cursor;
+                for _ in 0..*count {
+                    value = read_pointer(view, value)?;
+                }
+                value
             }
+        };
+
+        if !view.offset_valid(cursor) {
+            warn!("[SMTools] Resolve step '{0}' left the view at {1:#08X}", step.describe(), cursor);
         }
-    }
 
+        hops.push(cursor);
+    }
 
-    return matches;
-}
\ No newline at end of file
+    return Ok(hops);
+}
@@ -0,0 +1 @@
+pub use smtools_core::signature::*;
@@ -0,0 +1,141 @@
+
+//  Store:
+//  Generated signatures, kept in the view's metadata so they are saved with the .bndb.
+//  Stored as one JSON string under a single key.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use binaryninja::binaryview::BinaryView;
+use serde::{Deserialize, Serialize};
+use crate::gamedata::writer::{write_address_entry, write_signature_entry, KeyValuesWriter};
+use crate::signatures::dialect::Dialect;
+use crate::signatures::options::WildcardPolicy;
+use crate::signatures::scan::{find_signature, resolve_match};
+use crate::signatures::signature::{parse_full_signature, Signature};
+use crate::utils::metadata::{load_json, store_json};
+
+const METADATA_KEY: &str = "smtools.signatures";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredSignature {
+    pub name: String,
+    //  What the signature finds once resolved
+    pub target: u64,
+    //  Generic bytes and resolve chain, see Signature::to_str_full
+    pub signature: String,
+    //  Dialect used when exporting this entry on its own
+    pub format: String,
+    pub policy: String,
+    //  Seconds since the Unix epoch
+    pub created: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum VerifyStatus {
+    Ok,
+    Missing,
+    Ambiguous(usize),
+    //  Unique, but resolves somewhere other than the stored target
+    Moved(u64),
+    Invalid(String),
+}
+
+impl VerifyStatus
+{
+    pub fn describe(&self) -> String {
+        match &self {
+            VerifyStatus::Ok => "ok".to_owned(),
+            VerifyStatus::Missing => "MISSING".to_owned(),
+            VerifyStatus::Ambiguous(count) => format!("AMBIGUOUS ({0}+ matches)", count),
+            VerifyStatus::Moved(address) => format!("MOVED (now {0:#08X})", address),
+            VerifyStatus::Invalid(reason) => format!("INVALID ({0})", reason),
+        }
+    }
+}
+
+impl StoredSignature
+{
+    pub fn parse(&self) -> Result<Signature, String> {
+        return parse_full_signature(self.signature.to_owned());
+    }
+}
+
+pub fn load_signatures(view: &BinaryView) -> Vec<StoredSignature> {
+    return load_json(view, METADATA_KEY).unwrap_or_default();
+}
+
+pub fn save_signatures(view: &BinaryView, stored: &Vec<StoredSignature>) {
+    store_json(view, METADATA_KEY, stored);
+}
+
+//  Add a generated signature, replacing any earlier one with the same name.
+//  `dialect` is the format the signature was shown to the user in.
+pub fn remember_signature(view: &BinaryView, name: &str, target: u64, signature: &Signature, dialect: Dialect, policy: WildcardPolicy) {
+    let created = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    let mut stored = load_signatures(view);
+    stored.retain(|entry| entry.name != name);
+    stored.push(StoredSignature {
+        name: name.to_owned(),
+        target,
+        signature: signature.to_str_full(),
+        format: dialect.name().to_owned(),
+        policy: policy.name().to_owned(),
+        created,
+    });
+
+    save_signatures(view, &stored);
+}
+
+pub fn verify_stored(view: &BinaryView, entry: &StoredSignature) -> VerifyStatus {
+    let signature = match entry.parse() {
+        Ok(signature) => signature,
+        Err(reason) => return VerifyStatus::Invalid(reason),
+    };
+
+    let matches = find_signature(&signature.bytes, view, 2);
+    if matches.len() == 0 {
+        return VerifyStatus::Missing;
+    }
+    if matches.len() > 1 {
+        return VerifyStatus::Ambiguous(matches.len());
+    }
+
+    let resolved = match resolve_match(view, matches[0], &signature.resolve) {
+        Ok(hops) => hops.last().copied().unwrap_or(matches[0]),
+        Err(reason) => return VerifyStatus::Invalid(reason),
+    };
+
+    if resolved != entry.target {
+        return VerifyStatus::Moved(resolved);
+    }
+    return VerifyStatus::Ok;
+}
+
+pub fn format_stored_gamedata(view: &BinaryView, stored: &Vec<StoredSignature>) -> Result<String, String> {
+    let mut writer = KeyValuesWriter::new();
+    let mut resolved: Vec<(&StoredSignature, Signature)> = Vec::new();
+
+    writer.open("Signatures");
+    for entry in stored.iter() {
+        let signature = entry.parse()?;
+        write_signature_entry(&mut writer, view, &entry.name, &signature);
+        if signature.resolve.len() > 0 {
+            resolved.push((entry, signature));
+        }
+    }
+    writer.close();
+
+    if resolved.len() > 0 {
+        writer.open("Addresses");
+        for (entry, signature) in resolved.iter() {
+            write_address_entry(&mut writer, view, &entry.name, &entry.name, signature)?;
+        }
+        writer.close();
+    }
+
+    return Ok(writer.finish());
+}
+
+pub fn format_stored_json(stored: &Vec<StoredSignature>) -> Result<String, String> {
+    return serde_json::to_string_pretty(stored).map_err(|err| err.to_string());
+}
@@ -0,0 +1,216 @@
+
+//  Survey:
+//  Estimate, for every function in the view, how hard it is to sign from its entry.
+//  Uses the index when one can be loaded or built, since this asks once per function.
+
+use binaryninja::backgroundtask::BackgroundTask;
+use binaryninja::binaryview::{BinaryView, BinaryViewExt};
+use binaryninja::function::Function;
+use log::{info, warn};
+use crate::signatures::index::load_or_build_index;
+use crate::signatures::linear_generate::linear_generate_unique;
+use crate::signatures::minimise::count_concrete;
+use crate::settings::wildcard_policy;
+use crate::signatures::options::{GenerateOptions, Hardening};
+use crate::signatures::xref_generate::xref_generate_signature_with;
+use crate::utils::function::find_address_base;
+use crate::utils::tags::{clear_function_tags, set_function_tag};
+
+pub const UNSIGNABLE_TAG: &str = "SMTools Unsignable";
+const UNSIGNABLE_ICON: &str = "🚫";
+
+pub struct SurveyRow {
+    pub name: String,
+    pub address: u64,
+    //  Bytes in the shortest unique signature, None if there is none
+    pub length: Option<usize>,
+    pub concrete: usize,
+    pub xref: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum SurveySort {
+    Length,
+    WildcardRatio,
+    Name,
+    Address,
+}
+
+impl SurveyRow
+{
+    pub fn signable(&self) -> bool {
+        return self.length.is_some();
+    }
+
+    pub fn wildcard_ratio(&self) -> f64 {
+        return match self.length {
+            Some(length) if length > 0 => 1.0 - (self.concrete as f64 / length as f64),
+            _ => 0.0,
+        };
+    }
+}
+
+impl SurveySort
+{
+    pub const ALL: [SurveySort; 4] = [SurveySort::Length, SurveySort::WildcardRatio, SurveySort::Name, SurveySort::Address];
+
+    pub fn name(&self) -> &'static str {
+        match &self {
+            SurveySort::Length => "signature length",
+            SurveySort::WildcardRatio => "wildcard ratio",
+            SurveySort::Name => "name",
+            SurveySort::Address => "address",
+        }
+    }
+}
+
+pub fn survey_function(view: &BinaryView, buf: &Vec<u8>, func: &Function) -> SurveyRow {
+    let mut row = SurveyRow {
+        name: func.symbol().full_name().to_string(),
+        address: func.start(),
+        length: None,
+        concrete: 0,
+        xref: false,
+        reason: None,
+    };
+    let policy = wildcard_policy(view);
+
+    let direct = match linear_generate_unique(view, buf, func.start(), func.to_owned(), policy) {
+        Ok(sig) => {
+            row.length = Some(sig.len());
+            row.concrete = count_concrete(&sig);
+            return row;
+        }
+        Err(reason) => reason,
+    };
+
+    //  Only the shortest unique call site matters here, so skip hardening and minimising
+    let options = GenerateOptions { policy, hardening: Hardening::Instructions(0), minimise: false };
+    match xref_generate_signature_with(view, buf, func.start(), &options) {
+        Ok(found) => {
+            row.length = Some(found.signature.bytes.len());
+            row.concrete = count_concrete(&found.signature.bytes);
+            row.xref = true;
+        }
+        Err(reason) => row.reason = Some(format!("{0}; xref: {1}", direct, reason)),
+    }
+
+    return row;
+}
+
+//  Survey every function. Stops early, keeping what was done, if the task is cancelled.
+pub fn survey_view(view: &BinaryView, buf: &Vec<u8>, task: Option<&BackgroundTask>) -> Vec<SurveyRow> {
+    if let Err(reason) = load_or_build_index(view, buf) {
+        warn!("[SMTools] Surveying without an index, this will be slow: {0}", reason);
+    }
+
+    let functions = view.functions();
+    let total = functions.len();
+    let mut rows = Vec::new();
+
+    for (index, func) in functions.iter().enumerate() {
+        if let Some(task) = task {
+            if task.is_cancelled() {
+                info!("[SMTools] Survey cancelled after {0} of {1} functions", index, total);
+                break;
+            }
+            task.set_progress_text(format!("SMTools: surveying {0}/{1}", index + 1, total));
+        }
+
+        rows.push(survey_function(view, buf, func.as_ref()));
+    }
+
+    return rows;
+}
+
+pub fn sort_survey(rows: &mut Vec<SurveyRow>, sort: SurveySort) {
+    match sort {
+        //  Unsignable functions sort last
+        SurveySort::Length => rows.sort_by_key(|r| (r.length.is_none(), r.length.unwrap_or(0), r.address)),
+        SurveySort::WildcardRatio => rows.sort_by(|a, b| b.wildcard_ratio().total_cmp(&a.wildcard_ratio()).then(a.address.cmp(&b.address))),
+        SurveySort::Name => rows.sort_by(|a, b| a.name.cmp(&b.name)),
+        SurveySort::Address => rows.sort_by_key(|r| r.address),
+    }
+}
+
+//  Tag unsignable functions, and clear the tag from ones that can be signed now
+pub fn tag_unsignable(view: &BinaryView, rows: &Vec<SurveyRow>) -> usize {
+    let mut tagged = 0;
+
+    for row in rows.iter() {
+        let func = match find_address_base(view, row.address) {
+            Ok(func) => func,
+            Err(_) => continue,
+        };
+
+        if row.signable() {
+            clear_function_tags(view, func.as_ref(), UNSIGNABLE_TAG);
+            continue;
+        }
+
+        let reason = row.reason.to_owned().unwrap_or_default();
+        match set_function_tag(view, func.as_ref(), UNSIGNABLE_TAG, UNSIGNABLE_ICON, &reason) {
+            Ok(()) => tagged += 1,
+            Err(msg) => warn!("[SMTools] Failed to tag {0}: {1}", row.name, msg),
+        }
+    }
+
+    return tagged;
+}
+
+pub fn format_survey_table(rows: &Vec<SurveyRow>) -> String {
+    let width = rows.iter().map(|r| r.name.len()).max().unwrap_or(0).max(4);
+    let mut table = String::new();
+
+    table.push_str(&format!("{0:<width$}  {1:<10}  {2:>6}  {3:>9}  {4:<4}  {5}\n", "Name", "Address", "Length", "Wildcards", "Xref", "Reason", width = width));
+
+    for row in rows.iter() {
+        table.push_str(&format!("{0:<width$}  {1:<#10x}  {2:>6}  {3:>8.1}%  {4:<4}  {5}\n",
+            row.name,
+            row.address,
+            row.length.map(|l| l.to_string()).unwrap_or("-".to_owned()),
+            row.wildcard_ratio() * 100.0,
+            if row.xref { "yes" } else { "no" },
+            row.reason.to_owned().unwrap_or_default(),
+            width = width));
+    }
+
+    return table;
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains(|c| c == ',' || c == '"' || c == '\n') {
+        return format!("\"{0}\"", text.replace('"', "\"\""));
+    }
+    return text.to_owned();
+}
+
+pub fn format_survey_csv(rows: &Vec<SurveyRow>) -> String {
+    let mut csv = String::from("name,address,length,wildcard_ratio,xref,reason\n");
+
+    for row in rows.iter() {
+        csv.push_str(&format!("{0},{1:#x},{2},{3:.3},{4},{5}\n",
+            csv_field(&row.name),
+            row.address,
+            row.length.map(|l| l.to_string()).unwrap_or_default(),
+            row.wildcard_ratio(),
+            row.xref,
+            csv_field(&row.reason.to_owned().unwrap_or_default())));
+    }
+
+    return csv;
+}
+
+pub fn format_survey_json(rows: &Vec<SurveyRow>) -> Result<String, String> {
+    let entries: Vec<serde_json::Value> = rows.iter().map(|row| serde_json::json!({
+        "name": row.name,
+        "address": format!("{0:#x}", row.address),
+        "length": row.length,
+        "wildcard_ratio": row.wildcard_ratio(),
+        "xref": row.xref,
+        "reason": row.reason,
+    })).collect();
+
+    return serde_json::to_string_pretty(&entries).map_err(|err| err.to_string());
+}
@@ -0,0 +1,162 @@
+
+//  Xref generate:
+//  For functions too small to sign directly, sign a call site instead
+//  and record how to get from the call site back to the function.
+
+use std::time::Instant;
+use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::interaction::show_message_box;
+use log::{info, warn};
+use crate::gamedata::writer::format_address_gamedata;
+use crate::report::{address_link, Report};
+use crate::signatures::linear_generate::linear_generate_signature_with;
+use crate::settings::{default_dialect, generate_options};
+use crate::signatures::dialect::log_signature;
+use crate::signatures::options::GenerateOptions;
+use crate::signatures::scan::find_in_buffer;
+use crate::signatures::sigbyte::SigByte;
+use crate::signatures::store::remember_signature;
+use crate::signatures::signature::{ResolveStep, Signature};
+use crate::utils::function::{find_address_base, read_view};
+
+pub struct XrefSignature {
+    pub caller: u64,
+    pub signature: Signature,
+}
+
+//  Returns the offset of the rel32 within the instruction at `site`,
+//  if it is a direct call/jmp to `target`
+fn rel32_branch_to(view: &BinaryView, site: u64, target: u64) -> Option<u64> {
+    let bytes = view.read_vec(site, 5);
+    if bytes.len() != 5 {
+        return None;
+    }
+
+    //  E8: call rel32, E9: jmp rel32
+    if bytes[0] != 0xE8 && bytes[0] != 0xE9 {
+        return None;
+    }
+
+    let rel = i32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+    let destination = (site as i64 + 5 + rel as i64) as u64;
+
+    if destination != target {
+        return None;
+    }
+
+    return Some(1);
+}
+
+pub fn xref_generate_signature(view: &BinaryView, target: u64) -> Result<XrefSignature, String> {
+    let buf = read_view(view);
+
+    return xref_generate_signature_with(view, &buf, target, &generate_options(view));
+}
+
+pub fn xref_generate_signature_with(view: &BinaryView, buf: &Vec<u8>, target: u64, options: &GenerateOptions) -> Result<XrefSignature, String> {
+    let refs = view.get_code_refs(target);
+
+    if refs.len() == 0 {
+        return Err("No code references to the target".to_string());
+    }
+
+    let mut best: Option<XrefSignature> = None;
+
+    for reference in refs.iter() {
+        let site = reference.address;
+
+        let rel_offset = match rel32_branch_to(view, site, target) {
+            Some(rel_offset) => rel_offset,
+            None => {
+                info!("[SMTools] Skipping xref at {0:#08X}: not a rel32 call/jmp", site);
+                continue;
+            }
+        };
+
+        let func = match find_address_base(view, site) {
+            Ok(func) => func,
+            Err(reason) => {
+                info!("[SMTools] Skipping xref at {0:#08X}: {1}", site, reason);
+                continue;
+            }
+        };
+
+        match linear_generate_signature_with(view, buf, site, func.to_owned(), options) {
+            Ok(mut bytes) => {
+                //  The displacement is different at every call site, so never match on it
+                for index in rel_offset..(rel_offset + 4) {
+                    if (index as usize) < bytes.len() {
+                        bytes[index as usize] = SigByte::Wildcard;
+                    }
+                }
+
+                //  Uniqueness was proven with the displacement concrete
+                if find_in_buffer(&bytes, buf, view.start(), 2).len() != 1 {
+                    info!("[SMTools] Skipping xref at {0:#08X}: not unique with the displacement wildcarded", site);
+                    continue;
+                }
+
+                let better = match &best {
+                    Some(current) => bytes.len() < current.signature.bytes.len(),
+                    None => true,
+                };
+
+                if better {
+                    let resolve = vec![ResolveStep::Offset(rel_offset as i64), ResolveStep::Rel32];
+                    best = Some(XrefSignature { caller: site, signature: Signature::with_resolve(bytes, resolve) });
+                }
+            }
+            Err(reason) => {
+                info!("[SMTools] Skipping xref at {0:#08X}: {1}", site, reason);
+            }
+        }
+    }
+
+    return match best {
+        Some(found) => Ok(found),
+        None => Err("No call site could be signed".to_string()),
+    };
+}
+
+pub fn generate_and_print_xref_signature(view: &BinaryView, target: u64) {
+    if !view.offset_valid(target) {
+        warn!("[SMTools] Invalid Address");
+        return;
+    }
+
+    let name = match find_address_base(view, target) {
+        Ok(func) => func.symbol().full_name().to_string(),
+        Err(_) => format!("sub_{0:x}", target),
+    };
+
+    let options = generate_options(view);
+    let now = Instant::now();
+    let result = xref_generate_signature_with(view, &read_view(view), target, &options);
+    info!("[SMTools] Xref scan completed in {0}ms", now.elapsed().as_millis());
+
+    match result {
+        Ok(found) => {
+            info!("[SMTools] Xref signature for '{0}' via call site {1:#08X}", name, found.caller);
+            log_signature(view, &found.signature.bytes);
+            remember_signature(view, &name, target, &found.signature, default_dialect(view), options.policy);
+
+            Report::new("SMTools: Xref signature")
+                .note(&format!("Signed through the call site at {0}", address_link(found.caller)))
+                .signature(&name, target, &found.signature)
+                .timing("Xref scan", now.elapsed())
+                .publish(view);
+            for (index, step) in found.signature.resolve.iter().enumerate() {
+                info!("[SMTools] Resolve {0}: {1}", index + 1, step.describe());
+            }
+
+            match format_address_gamedata(view, &name, &found.signature) {
+                Ok(gamedata) => info!("[SMTools] Gamedata:\n{0}", gamedata),
+                Err(reason) => warn!("[SMTools] Failed to format gamedata: {0}", reason),
+            }
+        }
+        Err(reason) => {
+            warn!("[SMTools] Failed to get xref signature for '{0}': '{1}'", name, reason);
+            show_message_box("SMTools", &format!("Failed to get a cross-reference signature: {0}", reason), binaryninja::binaryninjacore_sys::BNMessageBoxButtonSet::OKButtonSet, binaryninja::binaryninjacore_sys::BNMessageBoxIcon::WarningIcon);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::ffi::{CStr, CString};
+use binaryninja::binaryninjacore_sys::{BNFreeString, BNGetCommentForAddress, BNGetInstructionHighlight, BNHighlightColor, BNHighlightColorStyle, BNHighlightStandardColor, BNSetCommentForAddress, BNSetUserInstructionHighlight};
+use binaryninja::function::Function;
+use serde::{Deserialize, Serialize};
+use crate::monkey::arch::create_monkey_arch;
+use crate::monkey::function::create_monkey_function;
+
+//  Comment lines written by SMTools start with this, so they can be replaced without touching the rest
+const ANNOTATION_PREFIX: &str = "[SMTools] ";
+
+const STYLES: [BNHighlightColorStyle; 3] = [
+    BNHighlightColorStyle::StandardHighlightColor,
+    BNHighlightColorStyle::MixedHighlightColor,
+    BNHighlightColorStyle::CustomHighlightColor,
+];
+
+const STANDARD_COLORS: [BNHighlightStandardColor; 10] = [
+    BNHighlightStandardColor::NoHighlightColor,
+    BNHighlightStandardColor::BlueHighlightColor,
+    BNHighlightStandardColor::GreenHighlightColor,
+    BNHighlightStandardColor::CyanHighlightColor,
+    BNHighlightStandardColor::RedHighlightColor,
+    BNHighlightStandardColor::MagentaHighlightColor,
+    BNHighlightStandardColor::YellowHighlightColor,
+    BNHighlightStandardColor::OrangeHighlightColor,
+    BNHighlightStandardColor::WhiteHighlightColor,
+    BNHighlightStandardColor::BlackHighlightColor,
+];
+
+//  An instruction highlight as it can be kept in metadata, to be put back later
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct SavedHighlight {
+    style: u32,
+    color: u32,
+    mix_color: u32,
+    mix: u8,
+    r: u8,
+    g: u8,
+    b: u8,
+    alpha: u8,
+}
+
+fn standard_color(value: u32) -> BNHighlightStandardColor {
+    return STANDARD_COLORS.into_iter().find(|color| *color as u32 == value).unwrap_or(BNHighlightStandardColor::NoHighlightColor);
+}
+
+impl SavedHighlight
+{
+    fn from_color(color: &BNHighlightColor) -> SavedHighlight {
+        return SavedHighlight {
+            style: color.style as u32,
+            color: color.color as u32,
+            mix_color: color.mixColor as u32,
+            mix: color.mix,
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            alpha: color.alpha,
+        };
+    }
+
+    fn to_color(&self) -> BNHighlightColor {
+        return BNHighlightColor {
+            style: STYLES.into_iter().find(|style| *style as u32 == self.style).unwrap_or(BNHighlightColorStyle::StandardHighlightColor),
+            color: standard_color(self.color),
+            mixColor: standard_color(self.mix_color),
+            mix: self.mix,
+            r: self.r,
+            g: self.g,
+            b: self.b,
+            alpha: self.alpha,
+        };
+    }
+}
+
+fn set_highlight_color(func: &Function, address: u64, highlight: BNHighlightColor)
+{
+    let arch = func.arch();
+
+    let mFunc = create_monkey_function(func);
+    let mArch = create_monkey_arch(arch.as_ref());
+
+    unsafe {
+        BNSetUserInstructionHighlight(mFunc.handle, mArch.0, address, highlight);
+    }
+}
+
+//  Highlight an instruction in the linear and graph views, or clear it with None
+pub fn set_instruction_highlight(func: &Function, address: u64, color: Option<BNHighlightStandardColor>)
+{
+    let highlight = BNHighlightColor {
+        style: BNHighlightColorStyle::StandardHighlightColor,
+        color: color.unwrap_or(BNHighlightStandardColor::NoHighlightColor),
+        mixColor: BNHighlightStandardColor::NoHighlightColor,
+        mix: 0,
+        r: 0,
+        g: 0,
+        b: 0,
+        alpha: 255,
+    };
+
+    set_highlight_color(func, address, highlight);
+}
+
+//  The highlight an instruction has now, whoever set it
+pub fn get_instruction_highlight(func: &Function, address: u64) -> SavedHighlight
+{
+    let arch = func.arch();
+
+    let mFunc = create_monkey_function(func);
+    let mArch = create_monkey_arch(arch.as_ref());
+
+    let highlight = unsafe { BNGetInstructionHighlight(mFunc.handle, mArch.0, address) };
+    return SavedHighlight::from_color(&highlight);
+}
+
+pub fn restore_instruction_highlight(func: &Function, address: u64, saved: &SavedHighlight)
+{
+    set_highlight_color(func, address, saved.to_color());
+}
+
+//  Replace the SMTools lines of the comment at `address`, keeping anything the user wrote
+pub fn set_annotation(func: &Function, address: u64, lines: &[String])
+{
+    let mFunc = create_monkey_function(func);
+    let bnFunc = mFunc.handle;
+
+    let existing = unsafe {
+        let raw = BNGetCommentForAddress(bnFunc, address);
+        if raw.is_null() {
+            String::new()
+        }
+        else {
+            let text = CStr::from_ptr(raw).to_string_lossy().to_string();
+            BNFreeString(raw);
+            text
+        }
+    };
+
+    let mut kept: Vec<String> = existing.lines()
+        .filter(|line| !line.starts_with(ANNOTATION_PREFIX))
+        .map(|line| line.to_owned())
+        .collect();
+    for line in lines.iter() {
+        kept.push(format!("{0}{1}", ANNOTATION_PREFIX, line));
+    }
+
+    let comment = match CString::new(kept.join("\n")) {
+        Ok(comment) => comment,
+        Err(_) => return,
+    };
+
+    unsafe {
+        BNSetCommentForAddress(bnFunc, address, comment.as_ptr());
+    }
+}
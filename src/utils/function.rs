@@ -1,8 +1,16 @@
+use std::ops::{Deref, Range};
+use std::sync::{Arc, Mutex};
+use binaryninja::binaryninjacore_sys::{BNConstantReference, BNFreeConstantReferenceList, BNFreeRelocationRanges, BNGetConstantsReferencedByInstructionIfAvailable, BNGetRelocationRangesInRange};
 use binaryninja::binaryview::{BinaryView, BinaryViewBase, BinaryViewExt};
 use binaryninja::function::Function;
 use binaryninja::rc;
 use binaryninja::rc::Ref;
-use log::{debug, error};
+use log::error;
+use crate::monkey::arch::create_monkey_arch;
+use crate::monkey::binaryview::create_monkey_bv;
+use crate::monkey::function::create_monkey_function;
+use crate::settings::{scan_scope, ScanScope};
+use crate::signatures::index::content_hash;
 
 pub fn find_address_base(view: &BinaryView, offset: u64) -> Result<rc::Ref<Function>, &str> {
     let blocks = view.basic_blocks_containing(offset);
@@ -18,6 +26,57 @@ pub fn find_address_base(view: &BinaryView, offset: u64) -> Result<rc::Ref<Funct
     return Err("Address is not within function");
 }
 
+//  Constants referenced by the instruction at `offset`, as seen by analysis
+pub fn instruction_constants(base: &Function, offset: u64) -> Vec<BNConstantReference>
+{
+    let arch = base.arch();
+    let mut result = Vec::new();
+
+    let mFunc = create_monkey_function(base);
+    let bnFunc = mFunc.handle;
+
+    let mArch = create_monkey_arch(arch.as_ref());
+    let bnArch = mArch.0;
+
+    unsafe {
+        let mut size: usize = 0;
+        let constants = BNGetConstantsReferencedByInstructionIfAvailable(
+            bnFunc, bnArch, offset, &mut size,
+        );
+
+        for index in 0..size {
+            result.push(*constants.add(index));
+        }
+
+        BNFreeConstantReferenceList(constants);
+    }
+
+    return result;
+}
+
+//  Byte ranges the loader applies relocations to within [start, start + len)
+pub fn relocation_ranges(view: &BinaryView, start: u64, len: u64) -> Vec<Range<u64>>
+{
+    let mut result = Vec::new();
+
+    let mBv = create_monkey_bv(view);
+    let bnBv = mBv.handle;
+
+    unsafe {
+        let mut count: usize = 0;
+        let ranges = BNGetRelocationRangesInRange(bnBv, start, len, &mut count);
+
+        for index in 0..count {
+            let range = *ranges.add(index);
+            result.push(range.start..range.end);
+        }
+
+        BNFreeRelocationRanges(ranges);
+    }
+
+    return result;
+}
+
 pub fn find_func_end(func: &Function) -> u64
 {
     let mut func_end = 0;
@@ -27,7 +86,105 @@ pub fn find_func_end(func: &Function) -> u64
     return func_end;
 }
 
-pub fn read_view(view: &BinaryView) -> Vec<u8>
+//  What is known about a live buffer returned by read_view
+pub struct BufferInfo {
+    pub hash: u64,
+    //  Sorted (start, end) offsets of executable segments when only code is scanned.
+    //  Everything else was zeroed to keep offsets intact, so matches there aren't real.
+    code: Option<Vec<(usize, usize)>>,
+}
+
+impl BufferInfo
+{
+    //  Whether only part of the buffer is scanned
+    pub fn scoped(&self) -> bool {
+        return self.code.is_some();
+    }
+
+    //  Whether `len` bytes at `offset` lie within what is scanned
+    pub fn in_scope(&self, offset: u64, len: usize) -> bool {
+        let code = match &self.code {
+            Some(code) => code,
+            None => return true,
+        };
+
+        let offset = offset as usize;
+        let containing = code.partition_point(|(start, _)| *start <= offset);
+        if containing == 0 {
+            return false;
+        }
+
+        let (_, end) = code[containing - 1];
+        return offset.saturating_add(len) <= end;
+    }
+}
+
+//  Buffers handed out by read_view and not yet dropped, by (address, length) of their bytes.
+//  A live buffer's bytes can't move or change, so this identifies it without reading it.
+static LIVE_BUFFERS: Mutex<Vec<(usize, usize, Arc<BufferInfo>)>> = Mutex::new(Vec::new());
+
+//  The bytes of a view as read by read_view. Derefs to the bytes, and keeps what was
+//  worked out about them while reading so lookups don't have to go over them again.
+pub struct ViewBuffer {
+    bytes: Vec<u8>,
+}
+
+impl ViewBuffer
+{
+    fn new(bytes: Vec<u8>, code: Option<Vec<(usize, usize)>>) -> ViewBuffer {
+        let info = BufferInfo { hash: content_hash(&bytes), code };
+
+        if bytes.len() > 0 {
+            if let Ok(mut live) = LIVE_BUFFERS.lock() {
+                live.push((bytes.as_ptr() as usize, bytes.len(), Arc::new(info)));
+            }
+        }
+
+        return ViewBuffer { bytes };
+    }
+}
+
+impl Deref for ViewBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        return &self.bytes;
+    }
+}
+
+impl Drop for ViewBuffer {
+    fn drop(&mut self) {
+        let key = (self.bytes.as_ptr() as usize, self.bytes.len());
+        if let Ok(mut live) = LIVE_BUFFERS.lock() {
+            live.retain(|(address, len, _)| (*address, *len) != key);
+        }
+    }
+}
+
+//  Whether a match of `len` bytes at buffer offset `offset` is real, and not in a region
+//  read_view blanked. Buffers that didn't come from read_view are scanned whole.
+pub fn in_scan_scope(info: &Option<Arc<BufferInfo>>, offset: u64, len: usize) -> bool {
+    return match info {
+        Some(info) => info.in_scope(offset, len),
+        None => true,
+    };
+}
+
+//  What read_view worked out about `buf`, or None if it didn't come from read_view
+pub fn buffer_info(buf: &[u8]) -> Option<Arc<BufferInfo>> {
+    if buf.len() == 0 {
+        return None;
+    }
+
+    let key = (buf.as_ptr() as usize, buf.len());
+    let live = LIVE_BUFFERS.lock().ok()?;
+
+    return live.iter()
+        .find(|(address, len, _)| (*address, *len) == key)
+        .map(|(_, _, info)| info.to_owned());
+}
+
+pub fn read_view(view: &BinaryView) -> ViewBuffer
 {
     let mut buf = Vec::new();
     let start = view.start();
@@ -56,7 +213,6 @@ pub fn read_view(view: &BinaryView) -> Vec<u8>
 
             next = view.next_valid_offset_after(current);
             let difference = next - current;
-            debug!("[SMTools] [BufRead] Buf len: {0}, Len: {1}, Start: {2}, Next: {3}, Current: {4}", buf.len(), view.len(), start, next, current);
 
             if (next >= (start + (view.len() as u64)))
             {
@@ -66,9 +222,48 @@ pub fn read_view(view: &BinaryView) -> Vec<u8>
             for _ in 0..difference {
                 buf.push(0);
             }
-            debug!("[SMTools] [BufRead] AfterPad: {0}", buf.len())
         }
     }
 
-    return buf;
-}
\ No newline at end of file
+    let code = match scan_scope(view) {
+        ScanScope::Code => Some(blank_non_code(view, &mut buf)),
+        ScanScope::View => None,
+    };
+
+    return ViewBuffer::new(buf, code);
+}
+
+//  Zero everything outside executable segments, keeping offsets into the buffer intact.
+//  Returns the executable ranges, sorted and merged.
+fn blank_non_code(view: &BinaryView, buf: &mut Vec<u8>) -> Vec<(usize, usize)>
+{
+    let start = view.start();
+    let mut code: Vec<(usize, usize)> = view.segments().iter()
+        .filter(|segment| segment.executable())
+        .map(|segment| {
+            let range = segment.address_range();
+            (range.start.saturating_sub(start) as usize, range.end.saturating_sub(start) as usize)
+        })
+        .collect();
+    code.sort_unstable();
+
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (begin, end) in code.into_iter() {
+        let begin = begin.min(buf.len());
+        let end = end.min(buf.len());
+        match merged.last_mut() {
+            Some((_, last)) if begin <= *last => *last = (*last).max(end),
+            _ => merged.push((begin, end)),
+        }
+    }
+
+    let mut cursor = 0;
+    for (begin, end) in merged.iter() {
+        buf[cursor..*begin].fill(0);
+        cursor = *end;
+    }
+    let len = buf.len();
+    buf[cursor..len].fill(0);
+
+    return merged;
+}
@@ -0,0 +1,36 @@
+use binaryninja::interaction::{FormInputBuilder, FormResponses};
+
+//  Ask the user to pick one of several options, returning the chosen index
+pub fn get_choice_input(prompt: &str, title: &str, choices: &[&str]) -> Option<usize>
+{
+    let responses = FormInputBuilder::new()
+        .choice_field(prompt, choices, Some(0))
+        .get_form_input(title);
+
+    return match responses.first() {
+        Some(FormResponses::Index(index)) => Some(*index),
+        _ => None,
+    };
+}
+
+//  Ask for a line of text, pre-filled with `default` so it can be edited rather than retyped
+pub fn get_edited_text_input(label: &str, prompt: &str, title: &str, default: &str) -> Option<String>
+{
+    let responses = FormInputBuilder::new()
+        .label_field(label, None)
+        .text_field(prompt, Some(default))
+        .get_form_input(title);
+
+    return match responses.get(1) {
+        Some(FormResponses::String(text)) => Some(text.to_owned()),
+        _ => None,
+    };
+}
+
+//  Put text on the system clipboard
+pub fn copy_to_clipboard(text: &str) -> Result<(), String>
+{
+    let mut clipboard = arboard::Clipboard::new().map_err(|err| err.to_string())?;
+
+    return clipboard.set_text(text.to_owned()).map_err(|err| err.to_string());
+}
@@ -0,0 +1,32 @@
+use binaryninja::binaryview::{BinaryView, BinaryViewExt};
+use binaryninja::metadata::Metadata;
+use binaryninja::rc::Ref;
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+//  A value stored as JSON in the view's metadata (and so in the .bndb), or None if
+//  there is none. Unreadable values are logged and treated as missing.
+pub fn load_json<T: DeserializeOwned>(view: &BinaryView, key: &str) -> Option<T>
+{
+    let text = view.query_metadata(key)?.get_string().ok()?.to_string();
+
+    return match serde_json::from_str(&text) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("[SMTools] Ignoring unreadable metadata '{0}': {1}", key, err);
+            None
+        }
+    };
+}
+
+pub fn store_json<T: Serialize>(view: &BinaryView, key: &str, value: &T)
+{
+    match serde_json::to_string(value) {
+        Ok(text) => {
+            let metadata: Ref<Metadata> = Metadata::from(text.as_str());
+            view.store_metadata(key, metadata, false);
+        }
+        Err(err) => warn!("[SMTools] Failed to store metadata '{0}': {1}", key, err),
+    }
+}
@@ -1 +1,5 @@
-pub mod function;
\ No newline at end of file
+pub mod annotate;
+pub mod function;
+pub mod interaction;
+pub mod metadata;
+pub mod tags;
@@ -0,0 +1,189 @@
+use std::ffi::CString;
+use binaryninja::binaryninjacore_sys::{BNAddTag, BNAddTagType, BNAddUserAddressTag, BNAddUserDataTag, BNAddUserFunctionTag, BNCreateTag, BNCreateTagType, BNFreeTag, BNFreeTagReferences, BNFreeTagType, BNGetAllTagReferencesOfType, BNGetTagType, BNRemoveUserAddressTag, BNRemoveUserDataTag, BNRemoveUserFunctionTag, BNRemoveUserFunctionTagsOfType, BNTagReferenceType, BNTagType, BNTagTypeSetIcon, BNTagTypeSetName};
+use binaryninja::binaryview::BinaryView;
+use binaryninja::function::Function;
+use crate::monkey::arch::create_monkey_arch;
+use crate::monkey::binaryview::create_monkey_bv;
+use crate::monkey::function::create_monkey_function;
+
+//  Every address carrying a tag of this type, for address and function tags alike
+pub fn tagged_addresses(view: &BinaryView, tag_type: &str) -> Vec<u64>
+{
+    let mut addresses = Vec::new();
+
+    let name = match CString::new(tag_type) {
+        Ok(name) => name,
+        Err(_) => return addresses,
+    };
+
+    let mBv = create_monkey_bv(view);
+    let bnBv = mBv.handle;
+
+    unsafe {
+        let bnType = BNGetTagType(bnBv, name.as_ptr());
+        if bnType.is_null() {
+            return addresses;
+        }
+
+        let mut count: usize = 0;
+        let refs = BNGetAllTagReferencesOfType(bnBv, bnType, &mut count);
+
+        for index in 0..count {
+            addresses.push((*refs.add(index)).addr);
+        }
+
+        BNFreeTagReferences(refs, count);
+        BNFreeTagType(bnType);
+    }
+
+    addresses.sort_unstable();
+    addresses.dedup();
+
+    return addresses;
+}
+
+//  Look up a tag type, creating it with `icon` if the view doesn't have one yet.
+//  The caller owns the returned reference.
+unsafe fn get_or_create_tag_type(view: &BinaryView, tag_type: &CString, icon: &CString) -> *mut BNTagType
+{
+    let mBv = create_monkey_bv(view);
+    let bnBv = mBv.handle;
+
+    let existing = BNGetTagType(bnBv, tag_type.as_ptr());
+    if !existing.is_null() {
+        return existing;
+    }
+
+    let bnType = BNCreateTagType(bnBv);
+    BNTagTypeSetName(bnType, tag_type.as_ptr());
+    BNTagTypeSetIcon(bnType, icon.as_ptr());
+    BNAddTagType(bnBv, bnType);
+
+    return bnType;
+}
+
+//  Replace any tags of this type on the function with a single one carrying `data`
+pub fn set_function_tag(view: &BinaryView, func: &Function, tag_type: &str, icon: &str, data: &str) -> Result<(), String>
+{
+    let tag_type = CString::new(tag_type).map_err(|err| err.to_string())?;
+    let icon = CString::new(icon).map_err(|err| err.to_string())?;
+    let data = CString::new(data).map_err(|err| err.to_string())?;
+
+    let mBv = create_monkey_bv(view);
+    let bnBv = mBv.handle;
+
+    let mFunc = create_monkey_function(func);
+    let bnFunc = mFunc.handle;
+
+    unsafe {
+        let bnType = get_or_create_tag_type(view, &tag_type, &icon);
+        BNRemoveUserFunctionTagsOfType(bnFunc, bnType);
+
+        let bnTag = BNCreateTag(bnType, data.as_ptr());
+        BNAddTag(bnBv, bnTag, true);
+        BNAddUserFunctionTag(bnFunc, bnTag);
+
+        BNFreeTag(bnTag);
+        BNFreeTagType(bnType);
+    }
+
+    return Ok(());
+}
+
+pub fn clear_function_tags(view: &BinaryView, func: &Function, tag_type: &str)
+{
+    let tag_type = match CString::new(tag_type) {
+        Ok(tag_type) => tag_type,
+        Err(_) => return,
+    };
+
+    let mBv = create_monkey_bv(view);
+    let bnBv = mBv.handle;
+
+    let mFunc = create_monkey_function(func);
+    let bnFunc = mFunc.handle;
+
+    unsafe {
+        let bnType = BNGetTagType(bnBv, tag_type.as_ptr());
+        if bnType.is_null() {
+            return;
+        }
+
+        BNRemoveUserFunctionTagsOfType(bnFunc, bnType);
+        BNFreeTagType(bnType);
+    }
+}
+
+//  Tag an address, as an address tag inside a function or a data tag anywhere else
+pub fn add_address_tag(view: &BinaryView, func: Option<&Function>, address: u64, tag_type: &str, icon: &str, data: &str) -> Result<(), String>
+{
+    let tag_type = CString::new(tag_type).map_err(|err| err.to_string())?;
+    let icon = CString::new(icon).map_err(|err| err.to_string())?;
+    let data = CString::new(data).map_err(|err| err.to_string())?;
+
+    let mBv = create_monkey_bv(view);
+    let bnBv = mBv.handle;
+
+    unsafe {
+        let bnType = get_or_create_tag_type(view, &tag_type, &icon);
+        let bnTag = BNCreateTag(bnType, data.as_ptr());
+        BNAddTag(bnBv, bnTag, true);
+
+        match func {
+            Some(func) => {
+                let mFunc = create_monkey_function(func);
+                let arch = func.arch();
+                let mArch = create_monkey_arch(arch.as_ref());
+                BNAddUserAddressTag(mFunc.handle, mArch.0, address, bnTag);
+            }
+            None => BNAddUserDataTag(bnBv, address, bnTag),
+        }
+
+        BNFreeTag(bnTag);
+        BNFreeTagType(bnType);
+    }
+
+    return Ok(());
+}
+
+//  Remove every user tag of this type, returning how many were removed
+pub fn clear_tags_of_type(view: &BinaryView, tag_type: &str) -> usize
+{
+    let tag_type = match CString::new(tag_type) {
+        Ok(tag_type) => tag_type,
+        Err(_) => return 0,
+    };
+
+    let mBv = create_monkey_bv(view);
+    let bnBv = mBv.handle;
+    let mut removed = 0;
+
+    unsafe {
+        let bnType = BNGetTagType(bnBv, tag_type.as_ptr());
+        if bnType.is_null() {
+            return 0;
+        }
+
+        let mut count: usize = 0;
+        let refs = BNGetAllTagReferencesOfType(bnBv, bnType, &mut count);
+
+        for index in 0..count {
+            let reference = *refs.add(index);
+            if reference.autoDefined {
+                continue;
+            }
+
+            match reference.refType {
+                BNTagReferenceType::AddressTagReference => BNRemoveUserAddressTag(reference.func, reference.arch, reference.addr, reference.tag),
+                BNTagReferenceType::FunctionTagReference => BNRemoveUserFunctionTag(reference.func, reference.tag),
+                BNTagReferenceType::DataTagReference => BNRemoveUserDataTag(bnBv, reference.addr, reference.tag),
+            }
+            removed += 1;
+        }
+
+        BNFreeTagReferences(refs, count);
+        BNFreeTagType(bnType);
+    }
+
+    return removed;
+}